@@ -0,0 +1,117 @@
+//! Compares parsing a large response with [`proxies_from_json_streaming`], which drops proxies with
+//! an unknown country as they're parsed, against collecting every proxy first and filtering after.
+//!
+//! Run with `cargo bench --bench streaming`. The peak memory of each approach is printed before the
+//! timings.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use iso_country::Country;
+use lead_oxide::proxy::{proxies_from_json_streaming, Proxy};
+
+// Tracks the peak number of allocated bytes
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const PROXIES: usize = 10_000;
+
+// Half of the proxies have a country that the API doesn't fill in correctly
+fn response() -> String {
+    let proxies: Vec<_> = (0..PROXIES)
+        .map(|i| {
+            let country = if i % 2 == 0 { "US" } else { "EU" };
+            format!(
+                r#"{{
+                    "ipPort": "10.0.{0}.{1}:80",
+                    "ip": "10.0.{0}.{1}",
+                    "port": "80",
+                    "country": "{2}",
+                    "last_checked": "2020-12-13 20:06:41",
+                    "proxy_level": "elite",
+                    "type": "http",
+                    "speed": "10",
+                    "support": {{
+                        "https": 0,
+                        "get": 1,
+                        "post": 1,
+                        "cookies": 1,
+                        "referer": 1,
+                        "user_agent": 1,
+                        "google": 0
+                    }}
+                }}"#,
+                i / 256,
+                i % 256,
+                country
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{"data": [{}], "count": {}}}"#,
+        proxies.join(","),
+        PROXIES
+    )
+}
+
+// How filtering worked before streaming, parsing everything and only then dropping the unknown
+// countries
+fn collect_then_filter(json: &[u8]) -> Vec<Proxy> {
+    let mut proxies = proxies_from_json_streaming(json, true).unwrap();
+    proxies.retain(|proxy| proxy.country != Country::Unspecified);
+    proxies
+}
+
+fn streaming(json: &[u8]) -> Vec<Proxy> {
+    proxies_from_json_streaming(json, false).unwrap()
+}
+
+fn peak_bytes(parse: fn(&[u8]) -> Vec<Proxy>, json: &[u8]) -> usize {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    let proxies = parse(json);
+    assert_eq!(proxies.len(), PROXIES / 2);
+    PEAK.load(Ordering::SeqCst) - before
+}
+
+fn bench(c: &mut Criterion) {
+    let json = response().into_bytes();
+    println!(
+        "Peak memory for {} proxies: collect-then-filter {} KiB, streaming {} KiB",
+        PROXIES,
+        peak_bytes(collect_then_filter, &json) / 1024,
+        peak_bytes(streaming, &json) / 1024,
+    );
+
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("collect_then_filter", |b| {
+        b.iter(|| collect_then_filter(black_box(&json)))
+    });
+    group.bench_function("streaming", |b| b.iter(|| streaming(black_box(&json))));
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,207 @@
+//! [`ValidatedCache`][ValidatedCache] shares known-good proxies between
+//! [`Fetcher`][crate::fetcher::Fetcher]s.
+//!
+//! Wrapping a cache in an `Arc` and handing it to several fetchers lets workers reuse proxies that
+//! someone already validated instead of each one spending quota fetching and validating their own.
+
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crate::proxy::Proxy;
+
+use chrono::NaiveDateTime;
+
+/// A process-wide store of proxies that are known to work.
+///
+/// Proxies are deposited with [`insert`][ValidatedCache::insert] once they have been validated and
+/// are handed back out with [`take`][ValidatedCache::take]. Entries expire once their
+/// `last_checked` is older than the cache's `max_age`, so depositing a proxy with an updated
+/// `last_checked` is the way to mark it as freshly validated.
+///
+/// ```
+/// use lead_oxide::{cache::ValidatedCache, fetcher::Fetcher};
+/// use std::{sync::Arc, time::Duration};
+///
+/// let cache = Arc::new(ValidatedCache::new(Duration::from_secs(60 * 60)));
+/// let worker1 = Fetcher::default().with_cache(Arc::clone(&cache));
+/// let worker2 = Fetcher::default().with_cache(Arc::clone(&cache));
+/// ```
+#[derive(Debug)]
+pub struct ValidatedCache {
+    max_age: Duration,
+    proxies: Mutex<Vec<Proxy>>,
+}
+
+impl ValidatedCache {
+    /// Creates an empty cache where entries expire after `max_age`.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            proxies: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Deposits a validated proxy, replacing any entry with the same socket.
+    pub fn insert(&self, proxy: Proxy) {
+        let mut proxies = self.lock();
+        proxies.retain(|cached| cached.socket != proxy.socket);
+        proxies.push(proxy);
+    }
+
+    /// Removes and returns up to `amount` unexpired proxies.
+    pub fn take(&self, amount: usize) -> Vec<Proxy> {
+        self.take_matching(amount, |_| true)
+    }
+
+    /// Like [`take`][ValidatedCache::take], but only takes proxies where `matches` returns `true`.
+    /// Everything else is left in the cache for whoever wants it.
+    ///
+    /// ```
+    /// use lead_oxide::{cache::ValidatedCache, types::Protocol};
+    /// use std::time::Duration;
+    ///
+    /// let cache = ValidatedCache::new(Duration::from_secs(60 * 60));
+    /// let socks = cache.take_matching(5, |proxy| proxy.protocol == Protocol::Socks5);
+    /// ```
+    pub fn take_matching<F>(&self, amount: usize, matches: F) -> Vec<Proxy>
+    where
+        F: Fn(&Proxy) -> bool,
+    {
+        let mut proxies = self.lock();
+        self.purge_expired(&mut proxies, now());
+
+        // Taken from the end like the rest of the library, keeping their order
+        let mut taken = Vec::new();
+        let mut i = proxies.len();
+        while i > 0 && taken.len() < amount {
+            i -= 1;
+            if matches(&proxies[i]) {
+                taken.push(proxies.remove(i));
+            }
+        }
+        taken.reverse();
+
+        taken
+    }
+
+    /// Returns `true` if an unexpired proxy with the same socket is cached.
+    pub fn contains(&self, proxy: &Proxy) -> bool {
+        let now = now();
+        self.lock()
+            .iter()
+            .any(|cached| cached.socket == proxy.socket && !self.is_expired(cached, now))
+    }
+
+    /// The number of unexpired proxies in the cache.
+    pub fn len(&self) -> usize {
+        let now = now();
+        self.lock()
+            .iter()
+            .filter(|cached| !self.is_expired(cached, now))
+            .count()
+    }
+
+    /// Returns `true` if there are no unexpired proxies in the cache.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Proxy>> {
+        // Nothing can be left half-updated by a panic so a poisoned lock is still fine to use
+        self.proxies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn purge_expired(&self, proxies: &mut Vec<Proxy>, now: NaiveDateTime) {
+        proxies.retain(|proxy| !self.is_expired(proxy, now));
+    }
+
+    fn is_expired(&self, proxy: &Proxy, now: NaiveDateTime) -> bool {
+        match (now - proxy.last_checked).to_std() {
+            Ok(age) => age > self.max_age,
+            // Checked in the future is as fresh as it gets
+            Err(_) => false,
+        }
+    }
+}
+
+// The API's timestamps are treated as UTC
+fn now() -> NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::proxy::test_util::proxy;
+
+    use chrono::Duration as ChronoDuration;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn proxy_checked_at(port: u16, last_checked: NaiveDateTime) -> Proxy {
+        proxy().with_port(port).with_last_checked(last_checked)
+    }
+
+    #[test]
+    fn expiry() {
+        let cache = ValidatedCache::new(HOUR);
+        let fresh = proxy_checked_at(1, now());
+        let stale = proxy_checked_at(2, now() - ChronoDuration::hours(2));
+
+        cache.insert(fresh.clone());
+        cache.insert(stale.clone());
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&fresh));
+        assert!(!cache.contains(&stale));
+
+        // Only the fresh proxy is handed out and the stale one gets dropped
+        assert_eq!(cache.take(2), vec![fresh]);
+        assert!(cache.is_empty());
+        assert!(cache.lock().is_empty());
+    }
+
+    #[test]
+    fn insert_replaces() {
+        let cache = ValidatedCache::new(HOUR);
+        let older = proxy_checked_at(1, now() - ChronoDuration::minutes(30));
+        let newer = proxy_checked_at(1, now());
+
+        cache.insert(older);
+        cache.insert(newer.clone());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.take(1), vec![newer]);
+    }
+
+    #[test]
+    fn take_partial() {
+        let cache = ValidatedCache::new(HOUR);
+        for port in 1..=3 {
+            cache.insert(proxy_checked_at(port, now()));
+        }
+
+        assert_eq!(cache.take(2).len(), 2);
+        assert_eq!(cache.take(2).len(), 1);
+        assert!(cache.take(2).is_empty());
+    }
+
+    #[test]
+    fn take_matching() {
+        let cache = ValidatedCache::new(HOUR);
+        for port in 1..=4 {
+            cache.insert(proxy_checked_at(port, now()));
+        }
+
+        let even = |proxy: &Proxy| proxy.socket.port().is_multiple_of(2);
+        let ports = |proxies: Vec<Proxy>| -> Vec<_> {
+            proxies.iter().map(|proxy| proxy.socket.port()).collect()
+        };
+        assert_eq!(ports(cache.take_matching(5, even)), [2, 4]);
+        // leaving the rest behind
+        assert_eq!(ports(cache.take(5)), [1, 3]);
+    }
+}
@@ -3,6 +3,20 @@ use std::time::Duration;
 // Note: pubproxy doesn't support https
 pub const API_URI: &str = "http://pubproxy.com/api/proxy?";
 pub const REPO_URI: &str = env!("CARGO_PKG_REPOSITORY");
+pub const USER_AGENT: &str = concat!("lead-oxide/", env!("CARGO_PKG_VERSION"));
+
+/// The minimum delay between requests without an API key. This is the delay that
+/// [`Fetcher`][crate::fetcher::Fetcher]s enforce between each request they make while sharing the
+/// keyless rate limit, so anything else hitting the API should wait at least this long too.
+pub const RATE_LIMIT_DELAY: Duration = Duration::from_millis(1_100);
 
 // Note: A shorter delay is used when testing
-pub const DELAY: Duration = Duration::from_millis(if cfg!(test) { 100 } else { 1_100 });
+pub const DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(100)
+} else {
+    RATE_LIMIT_DELAY
+};
+
+// How many requests in a row can come back without anything usable before a `Fetcher` gives up,
+// since client-side filtering (like deduping) can keep throwing away everything the API returns
+pub const MAX_EMPTY_FETCHES: usize = 3;
@@ -1,9 +1,15 @@
 //! Represents all the errors epressed by the API and library.
 //!
-//! These are divided into an `APIError` which represents all errors returned by pubproxy.com and
-//! `ParamError` which expresses any parameters that were invalid and can't be caught at compile time.
+//! These are divided into an `APIError` which represents all errors returned by pubproxy.com,
+//! `ParamError` which expresses any parameters that were invalid and can't be caught at compile time,
+//! `OptsError` for combinations of options that don't make sense together, and `ProxyParseError` for
+//! proxies returned by the API that couldn't be understood. `CheckError` is from checking a proxy
+//! with the `check` feature.
 
-use std::fmt;
+use std::{fmt, time::Duration};
+
+#[cfg(feature = "check")]
+use std::io;
 
 use crate::{constants, types::NaiveResponse};
 
@@ -11,25 +17,111 @@ use thiserror::Error;
 
 /// Represents an error with a parameter type.
 ///
-/// Currently the only types that can error are [`LastChecked`][crate::types::LastChecked] and
-/// [`TimeToConnect`][crate::types::TimeToConnect] since they are both bounded values which will
-/// error if the provided value is out of bounds.
+/// [`LastChecked`][crate::types::LastChecked] and [`TimeToConnect`][crate::types::TimeToConnect]
+/// are both bounded values which will error if the provided value is out of bounds, while parsing
+/// values from names like with [`Supports::from_names`][crate::proxy::Supports::from_names] will
+/// error on names that aren't recognized. Values that are valid, but not supported by the library
+/// like `Country::Unspecified` in [`Countries::try_country`][crate::types::Countries::try_country]
+/// are unsupported.
+///
+/// ```
+/// use lead_oxide::types::TimeToConnect;
+/// use std::{convert::TryFrom, time::Duration};
+///
+/// let err = TimeToConnect::try_from(Duration::from_secs(0)).unwrap_err();
+/// assert_eq!(err.to_string(), "'0 seconds' is outside bounds: 1 second to 60 seconds");
+/// ```
 #[derive(Error, Debug, PartialEq, Eq)]
-pub enum ParamError<T: PartialEq + fmt::Debug> {
-    #[error("'{value:?}' is outside bounds: {bounds:?}")]
+pub enum ParamError<T: PartialEq + fmt::Debug + fmt::Display> {
+    #[error("'{value}' is outside bounds: {} to {}", bounds.0, bounds.1)]
     OutOfBounds { bounds: (T, T), value: T },
+
+    #[error("'{value}' is not a recognized value")]
+    Unrecognized { value: T },
+
+    #[error("'{value}' is not supported here")]
+    Unsupported { value: T },
 }
 
-impl<T: PartialEq + fmt::Debug> ParamError<T> {
+impl<T: PartialEq + fmt::Debug + fmt::Display> ParamError<T> {
     pub fn out_of_bounds(value: T, bounds: (T, T)) -> Self {
         Self::OutOfBounds { value, bounds }
     }
+
+    pub fn unrecognized(value: T) -> Self {
+        Self::Unrecognized { value }
+    }
+
+    pub fn unsupported(value: T) -> Self {
+        Self::Unsupported { value }
+    }
+}
+
+/// Represents a combination of options that doesn't make sense from
+/// [`OptsBuilder::try_build`][crate::opts::OptsBuilder::try_build].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OptsError {
+    #[error("An empty allowlist doesn't filter anything, add countries to it or leave it unset")]
+    EmptyAllowList,
+
+    #[error("'{api_base}' isn't an http or https URL")]
+    InvalidApiBase { api_base: String },
+
+    #[error("The port range {start}..={end} is empty so no proxies would ever match")]
+    EmptyPortRange { start: u16, end: u16 },
+
+    #[error("Every country in the allowlist is excluded so no proxies would ever match")]
+    ExcludedAllowList,
+
+    #[error("Only allowing HTTP proxies that support POST but not HTTPS is contradictory")]
+    HttpPostWithoutHttps,
+}
+
+/// Represents a proxy from the API with a `field` that couldn't be parsed from `value`.
+///
+/// Proxies like this are skipped when parsing a response, but this is returned when converting a
+/// [`RawProxy`][crate::proxy::RawProxy] into a [`Proxy`][crate::proxy::Proxy].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid `{field}` for a proxy: '{value}'")]
+pub struct ProxyParseError {
+    pub field: &'static str,
+    pub value: String,
+}
+
+impl ProxyParseError {
+    pub(crate) fn new(field: &'static str, value: &str) -> Self {
+        Self {
+            field,
+            value: value.to_string(),
+        }
+    }
+}
+
+/// Represents a failure to check a proxy with [`Proxy::check`][crate::proxy::Proxy::check].
+///
+/// This is only available with the `check` feature enabled.
+#[cfg(feature = "check")]
+#[derive(Error, Debug)]
+pub enum CheckError {
+    #[error("Checking {what} isn't supported")]
+    Unsupported { what: &'static str },
+
+    #[error("Failed to connect to the proxy: {source}")]
+    Connect { source: io::Error },
+
+    #[error("Failed to reach the target through the proxy: {message}")]
+    Request { message: String },
 }
 
 /// Represents all possible errors returned by the API.
 ///
 /// Some variants should be entirely prevented by this library like `Client`, while others are
 /// expected from heavy use like `RateLimit` or from being too strict on parameters like `NoProxy`.
+///
+/// Variants with a `response` hold the API's response when the error came from one, and `None`
+/// when the library reports the error itself (like `NoProxy` once a fetcher runs dry).
+/// [`status`][ApiError::status] and [`body`][ApiError::body] get at the raw response for any
+/// variant.
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("Client Error ({status}): {text}\n This should be prevented, please raise an issue")]
@@ -38,69 +130,452 @@ pub enum ApiError {
     #[error("Internal Server Error ({status}): {text}")]
     Server { status: u16, text: String },
 
+    #[error("Failed to get a response from the API: {message}")]
+    Transport { message: String },
+
+    /// The API responded with JSON that doesn't match what the library expects, where `source`
+    /// says what was wrong with the response in `text`.
+    #[error("Failed to parse the API's response ({source}): {text}\n Consider raising an issue")]
+    Parse {
+        source: serde_json::Error,
+        text: String,
+    },
+
     #[error("Invalid API key, make sure your key is valid")]
-    ApiKey,
+    ApiKey { response: Option<NaiveResponse> },
 
     // TODO: mention fetchers from multiple sessions
+    /// `retry_after` is how long to wait before making another request. The API doesn't say so
+    /// this is always [`RATE_LIMIT_DELAY`][crate::fetcher::RATE_LIMIT_DELAY], but keep in mind
+    /// that the API may keep denying requests for much longer.
     #[error(
-        "You have exceeded the rate limit. This could be due to multiple programs using the API. \
- If this is not the case then sorry but the API hates you, consider raising an issue."
+        "You have exceeded the rate limit, wait at least {retry_after:?} before retrying. This \
+ could be due to multiple programs using the API. If this is not the case then sorry but the API \
+ hates you, consider raising an issue."
     )]
-    RateLimit,
+    RateLimit {
+        retry_after: Duration,
+        response: Option<NaiveResponse>,
+    },
 
     #[error("You have exhausted the daily limit of proxies.")]
-    DailyLimit,
+    DailyLimit { response: Option<NaiveResponse> },
 
     #[error("No matching proxies, consider broadening the parameters used")]
-    NoProxy,
+    NoProxy { response: Option<NaiveResponse> },
 
+    /// The API returned a message that isn't one of its known errors, which likely means that the
+    /// wording changed upstream and the library needs to catch up.
+    #[error(
+        "The API returned an unrecognized message, its wording may have changed. Consider raising \
+ an issue with the library: {}",
+        .response.text
+    )]
+    UnrecognizedMessage { response: NaiveResponse },
+
+    /// The API's response couldn't be made sense of at all, like an empty body.
     #[error("The API returned an unexpected message. Consider raising an issue with the library")]
-    Unknown,
+    Unknown { response: Option<NaiveResponse> },
+}
+
+impl ApiError {
+    /// Internal
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::Client { .. } => "Client",
+            Self::Server { .. } => "Server",
+            Self::Transport { .. } => "Transport",
+            Self::Parse { .. } => "Parse",
+            Self::ApiKey { .. } => "ApiKey",
+            Self::RateLimit { .. } => "RateLimit",
+            Self::DailyLimit { .. } => "DailyLimit",
+            Self::NoProxy { .. } => "NoProxy",
+            Self::UnrecognizedMessage { .. } => "UnrecognizedMessage",
+            Self::Unknown { .. } => "Unknown",
+        }
+    }
+
+    /// Whether the error can go away on its own by waiting and then sending the same request
+    /// again. This is the case for `RateLimit`, `Server`, and `Transport`.
+    ///
+    /// ```
+    /// use lead_oxide::errors::ApiError;
+    ///
+    /// assert!(ApiError::Transport { message: "Timed out".to_string() }.is_retryable());
+    /// assert!(!ApiError::NoProxy { response: None }.is_retryable());
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimit { .. } | Self::Server { .. } | Self::Transport { .. } => true,
+            Self::Client { .. }
+            | Self::Parse { .. }
+            | Self::ApiKey { .. }
+            | Self::DailyLimit { .. }
+            | Self::NoProxy { .. }
+            | Self::UnrecognizedMessage { .. }
+            | Self::Unknown { .. } => false,
+        }
+    }
+
+    /// Whether the error will keep happening no matter how long you wait or how the `Opts` are
+    /// changed, which is the case for `Client`, `ApiKey`, and `DailyLimit`. Note that errors can
+    /// be neither fatal nor retryable, like `NoProxy` which can be fixed by using broader `Opts`
+    /// along with `Parse` and `Unknown` where there's no telling.
+    ///
+    /// ```
+    /// use lead_oxide::errors::ApiError;
+    ///
+    /// assert!(ApiError::DailyLimit { response: None }.is_fatal());
+    /// assert!(!ApiError::NoProxy { response: None }.is_fatal());
+    /// ```
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Self::Client { .. } | Self::ApiKey { .. } | Self::DailyLimit { .. } => true,
+            Self::RateLimit { .. }
+            | Self::Server { .. }
+            | Self::Transport { .. }
+            | Self::Parse { .. }
+            | Self::NoProxy { .. }
+            | Self::UnrecognizedMessage { .. }
+            | Self::Unknown { .. } => false,
+        }
+    }
+
+    /// The status code of the API's response behind the error, if there was one.
+    ///
+    /// ```
+    /// use lead_oxide::{errors::ApiError, types::NaiveResponse};
+    ///
+    /// let err = ApiError::from(NaiveResponse::new(200, "No proxy".to_string()));
+    /// assert_eq!(err.status(), Some(200));
+    /// assert_eq!(err.body(), Some("No proxy"));
+    /// ```
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Client { status, .. } | Self::Server { status, .. } => Some(*status),
+            _ => self.response().map(|resp| resp.status),
+        }
+    }
+
+    /// The body of the API's response behind the error, if there was one.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            Self::Client { text, .. } | Self::Server { text, .. } | Self::Parse { text, .. } => {
+                Some(text)
+            }
+            _ => self.response().map(|resp| resp.text.as_str()),
+        }
+    }
+
+    fn response(&self) -> Option<&NaiveResponse> {
+        match self {
+            Self::ApiKey { response }
+            | Self::RateLimit { response, .. }
+            | Self::DailyLimit { response }
+            | Self::NoProxy { response }
+            | Self::Unknown { response } => response.as_ref(),
+            Self::UnrecognizedMessage { response } => Some(response),
+            Self::Client { .. }
+            | Self::Server { .. }
+            | Self::Transport { .. }
+            | Self::Parse { .. } => None,
+        }
+    }
+
+    /// Internal
+    pub(crate) fn with_response(mut self, naive_resp: NaiveResponse) -> Self {
+        match &mut self {
+            Self::ApiKey { response }
+            | Self::RateLimit { response, .. }
+            | Self::DailyLimit { response }
+            | Self::NoProxy { response }
+            | Self::Unknown { response } => *response = Some(naive_resp),
+            Self::UnrecognizedMessage { response } => *response = naive_resp,
+            Self::Client { .. }
+            | Self::Server { .. }
+            | Self::Transport { .. }
+            | Self::Parse { .. } => {}
+        }
+
+        self
+    }
 }
 
 impl From<NaiveResponse> for ApiError {
     fn from(naive_resp: NaiveResponse) -> Self {
-        let NaiveResponse { status, text } = naive_resp;
-
         // Some known errors get returned with varied `status` codes so match on response text first
         // then add context to unknown status codes
-        match Self::from(text.clone()) {
-            Self::Unknown => {
+        match Self::from(naive_resp.text.clone()) {
+            Self::UnrecognizedMessage { .. } | Self::Unknown { .. } => {
+                let NaiveResponse { status, text } = naive_resp;
                 if (400..500).contains(&status) {
                     Self::Client { status, text }
                 } else if (500..600).contains(&status) {
                     Self::Server { status, text }
+                } else if text.trim().is_empty() {
+                    // A successful response that couldn't be parsed
+                    Self::Unknown {
+                        response: Some(NaiveResponse::new(status, text)),
+                    }
                 } else {
-                    unreachable!(
-                        "Tried creating ApiError from valid response ({}). Please raise an issue \
-                         at {}.",
-                        status,
-                        constants::REPO_URI
-                    );
+                    Self::UnrecognizedMessage {
+                        response: NaiveResponse::new(status, text),
+                    }
                 }
             }
-            err => err,
+            known => known.with_response(naive_resp),
         }
     }
 }
 
-const INVALID_API_KEY: &str =
-    "Invalid API. Get your API to make unlimited requests at http://pubproxy.com/#premium";
-const RATE_LIMIT: &str = "We have to temporarily stop you. You're requesting proxies a little too \
-                          fast (2+ requests per second). Get your API to remove this limit at
-                          http://pubproxy.com/#premium";
-const DAILY_LIMIT: &str = "You reached the maximum 50 requests for today. Get your API to make \
-                           unlimited requests at http://pubproxy.com/#premium";
-const NO_PROXY: &str = "No proxy";
-
+/// Matches on the API's known error messages. There's no status code to go off of here, so any
+/// `response` is left as `None`.
+///
+/// Whitespace and case are ignored and only a stable part of each message is matched, so that
+/// small wording tweaks from the API don't go unnoticed as `Unknown`. Anything else is an
+/// `UnrecognizedMessage` that keeps `s` as the body of a `200` response, which is how the API
+/// sends back its messages when it isn't erroring.
 impl From<String> for ApiError {
     fn from(s: String) -> Self {
-        match s.as_str() {
-            INVALID_API_KEY => Self::ApiKey,
-            RATE_LIMIT => Self::RateLimit,
-            DAILY_LIMIT => Self::DailyLimit,
-            NO_PROXY => Self::NoProxy,
-            _ => Self::Unknown,
+        let normalized = s
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        if normalized.is_empty() {
+            Self::Unknown { response: None }
+        } else if normalized == "no proxy" {
+            Self::NoProxy { response: None }
+        } else if normalized.contains("invalid api") {
+            Self::ApiKey { response: None }
+        } else if normalized.contains("too fast") {
+            Self::RateLimit {
+                retry_after: constants::RATE_LIMIT_DELAY,
+                response: None,
+            }
+        } else if normalized.contains("requests for today") {
+            Self::DailyLimit { response: None }
+        } else {
+            Self::UnrecognizedMessage {
+                response: NaiveResponse::new(200, s),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{LastChecked, TimeToConnect};
+
+    // The API's current wording for each of its errors
+    const INVALID_API_KEY: &str =
+        "Invalid API. Get your API to make unlimited requests at http://pubproxy.com/#premium";
+    const RATE_LIMIT: &str = "We have to temporarily stop you. You're requesting proxies a little \
+                              too fast (2+ requests per second). Get your API to remove this \
+                              limit at http://pubproxy.com/#premium";
+    const DAILY_LIMIT: &str = "You reached the maximum 50 requests for today. Get your API to \
+                               make unlimited requests at http://pubproxy.com/#premium";
+    const NO_PROXY: &str = "No proxy";
+
+    #[test]
+    fn param_messages() {
+        assert_eq!(
+            TimeToConnect::from_secs(0).unwrap_err().to_string(),
+            "'0 seconds' is outside bounds: 1 second to 60 seconds"
+        );
+        assert_eq!(
+            LastChecked::from_minutes(90).unwrap_err().to_string(),
+            "'90 minutes' is outside bounds: 1 minute to 60 minutes"
+        );
+        assert_eq!(
+            ParamError::out_of_bounds(0, (1, u16::MAX)).to_string(),
+            "'0' is outside bounds: 1 to 65535"
+        );
+        assert_eq!(
+            ParamError::unrecognized("google".to_string()).to_string(),
+            "'google' is not a recognized value"
+        );
+        assert_eq!(
+            ParamError::unsupported("Unspecified".to_string()).to_string(),
+            "'Unspecified' is not supported here"
+        );
+    }
+
+    #[test]
+    fn classification() {
+        let text = || String::from("text");
+        let all = [
+            ApiError::Client {
+                status: 400,
+                text: text(),
+            },
+            ApiError::Server {
+                status: 500,
+                text: text(),
+            },
+            ApiError::Transport { message: text() },
+            ApiError::Parse {
+                source: serde_json::from_str::<u8>("").unwrap_err(),
+                text: text(),
+            },
+            ApiError::ApiKey { response: None },
+            ApiError::RateLimit {
+                retry_after: constants::RATE_LIMIT_DELAY,
+                response: None,
+            },
+            ApiError::DailyLimit { response: None },
+            ApiError::NoProxy { response: None },
+            ApiError::UnrecognizedMessage {
+                response: NaiveResponse::new(200, text()),
+            },
+            ApiError::Unknown { response: None },
+        ];
+
+        for err in &all {
+            // No wildcard so that new variants have to be added here
+            let (retryable, fatal) = match err {
+                ApiError::Client { .. } => (false, true),
+                ApiError::Server { .. } => (true, false),
+                ApiError::Transport { .. } => (true, false),
+                ApiError::Parse { .. } => (false, false),
+                ApiError::ApiKey { .. } => (false, true),
+                ApiError::RateLimit { .. } => (true, false),
+                ApiError::DailyLimit { .. } => (false, true),
+                ApiError::NoProxy { .. } => (false, false),
+                ApiError::UnrecognizedMessage { .. } => (false, false),
+                ApiError::Unknown { .. } => (false, false),
+            };
+            assert_eq!(err.is_retryable(), retryable, "{:?}", err);
+            assert_eq!(err.is_fatal(), fatal, "{:?}", err);
         }
     }
+
+    #[test]
+    fn rate_limit() {
+        let err = ApiError::from(NaiveResponse::new(429, RATE_LIMIT.to_string()));
+        match &err {
+            ApiError::RateLimit { retry_after, .. } => {
+                assert_eq!(*retry_after, constants::RATE_LIMIT_DELAY)
+            }
+            _ => panic!("Expected a rate limit error"),
+        }
+        assert!(err.to_string().contains("wait at least 1.1s"));
+    }
+
+    #[test]
+    fn real_messages() {
+        // Exactly as the API sends them, which is all on one line
+        let real = [
+            (
+                "We have to temporarily stop you. You're requesting proxies a little too fast (2+ requests per second). Get your API to remove this limit at http://pubproxy.com/#premium",
+                "RateLimit",
+            ),
+            (
+                "Invalid API. Get your API to make unlimited requests at http://pubproxy.com/#premium",
+                "ApiKey",
+            ),
+            (
+                "You reached the maximum 50 requests for today. Get your API to make unlimited requests at http://pubproxy.com/#premium",
+                "DailyLimit",
+            ),
+            ("No proxy", "NoProxy"),
+        ];
+
+        for &(text, kind) in &real {
+            assert!(!text.contains('\n'));
+            // Known messages win out over the status code
+            for &status in &[200, 429, 503] {
+                let err = ApiError::from(NaiveResponse::new(status, text.to_string()));
+                assert_eq!(err.kind(), kind, "{} ({})", text, status);
+            }
+        }
+
+        assert_eq!(real[0].0, RATE_LIMIT);
+        assert_eq!(real[1].0, INVALID_API_KEY);
+        assert_eq!(real[2].0, DAILY_LIMIT);
+        assert_eq!(real[3].0, NO_PROXY);
+    }
+
+    #[test]
+    fn raw_response() {
+        let resp = |status: u16, text: &str| NaiveResponse::new(status, text.to_string());
+
+        // Known messages keep the response they came from without changing the message
+        let err = ApiError::from(resp(429, RATE_LIMIT));
+        assert_eq!(err.status(), Some(429));
+        assert_eq!(err.body(), Some(RATE_LIMIT));
+        assert!(err
+            .to_string()
+            .starts_with("You have exceeded the rate limit"));
+
+        let err = ApiError::from(resp(403, INVALID_API_KEY));
+        assert!(matches!(err, ApiError::ApiKey { .. }));
+        assert_eq!(err.status(), Some(403));
+        assert_eq!(err.body(), Some(INVALID_API_KEY));
+
+        let err = ApiError::from(resp(200, DAILY_LIMIT));
+        assert!(matches!(err, ApiError::DailyLimit { .. }));
+        assert_eq!(err.status(), Some(200));
+
+        let err = ApiError::from(resp(200, "Something new"));
+        assert!(matches!(err, ApiError::UnrecognizedMessage { .. }));
+        assert_eq!(err.status(), Some(200));
+        assert_eq!(err.body(), Some("Something new"));
+
+        let err = ApiError::from(resp(200, ""));
+        assert!(matches!(err, ApiError::Unknown { .. }));
+        assert_eq!(err.status(), Some(200));
+        assert_eq!(err.body(), Some(""));
+
+        let err = ApiError::from(resp(502, "Bad Gateway"));
+        assert_eq!(err.status(), Some(502));
+        assert_eq!(err.body(), Some("Bad Gateway"));
+
+        // Nothing to report when there was no response
+        let err = ApiError::from(NO_PROXY.to_string());
+        assert!(matches!(err, ApiError::NoProxy { .. }));
+        assert_eq!(err.status(), None);
+        assert_eq!(err.body(), None);
+        assert_eq!(
+            ApiError::Transport {
+                message: "Timed out".to_string()
+            }
+            .status(),
+            None
+        );
+    }
+
+    #[test]
+    fn message_drift() {
+        let classify = |text: &str| ApiError::from(text.to_string()).kind();
+
+        // Whitespace and case don't matter
+        assert_eq!(classify("  No   proxy\n"), "NoProxy");
+        assert_eq!(classify("NO PROXY"), "NoProxy");
+        assert_eq!(classify(&INVALID_API_KEY.replace(' ', "\n\t")), "ApiKey");
+        assert_eq!(classify(&DAILY_LIMIT.replace(' ', "  ")), "DailyLimit");
+        assert_eq!(classify(RATE_LIMIT), "RateLimit");
+
+        // Only the stable part of the message has to stay the same
+        assert_eq!(
+            classify("Slow down! You're requesting proxies too fast (3+ requests per second)."),
+            "RateLimit"
+        );
+        assert_eq!(
+            classify("You reached the maximum 100 requests for today."),
+            "DailyLimit"
+        );
+        assert_eq!(classify("Invalid API key"), "ApiKey");
+
+        // Anything else is kept around to be reported
+        let err = ApiError::from("Please try again later".to_string());
+        assert!(matches!(
+            &err,
+            ApiError::UnrecognizedMessage { response } if response.text == "Please try again later"
+        ));
+        assert!(err.to_string().ends_with(": Please try again later"));
+        assert_eq!(classify(" \n "), "Unknown");
+    }
 }
@@ -4,22 +4,70 @@
 //! default will return any proxies on the listing.
 
 use std::{
-    sync::{Arc, Mutex},
-    thread,
-    time::Instant,
+    cmp,
+    collections::{HashMap, HashSet},
+    fmt, mem,
+    net::{AddrParseError, IpAddr, SocketAddr},
+    sync::{
+        mpsc::{self, RecvTimeoutError, SendError, Sender, SyncSender},
+        Arc, Mutex, MutexGuard, OnceLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use crate::{
+    cache::ValidatedCache,
     constants,
     errors::ApiError,
-    opts::Opts,
-    proxy::{proxies_from_json, Proxy},
-    types::NaiveResponse,
+    opts::{Filter, Format, Limit, Opts},
+    proxy::{proxies_from_json, proxies_from_txt, raw_proxies_from_json, Proxy, RawProxy},
+    types::{Level, NaiveResponse},
 };
 
+use chrono::NaiveDateTime;
+
+#[cfg(feature = "shuffle")]
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+pub use crate::constants::RATE_LIMIT_DELAY;
+
+#[cfg(feature = "async")]
+pub mod async_fetcher;
+pub mod transport;
+
+use transport::{set_timeouts, Transport, UreqTransport};
+
 lazy_static! {
-    static ref LAST_FETCHED: Arc<Mutex<Instant>> =
-        Arc::new(Mutex::new(Instant::now() - constants::DELAY));
+    static ref THROTTLE: Arc<Mutex<Throttle>> = Arc::new(Mutex::new(Throttle::new()));
+}
+
+/// Internal
+// Coordinates the delays between keyless requests. Each request waits out its own delay and the
+// delay of whoever made the previous request, whichever is longer, so a stricter delay is always
+// respected without sticking around once that fetcher stops making requests
+#[derive(Debug)]
+struct Throttle {
+    last_fetched: Instant,
+    last_delay: Duration,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Self {
+            last_fetched: Instant::now() - constants::DELAY,
+            last_delay: Duration::from_secs(0),
+        }
+    }
+
+    fn delay_for(&self, opts: &Opts) -> Duration {
+        self.last_delay.max(opts.request_delay())
+    }
+
+    fn fetched(&mut self, opts: &Opts) {
+        self.last_fetched = Instant::now();
+        self.last_delay = opts.request_delay();
+    }
 }
 
 /// The entrypoint into the API.
@@ -30,7 +78,172 @@ lazy_static! {
 #[derive(Clone, Debug)]
 pub struct Fetcher {
     opts: Opts,
+    api_keys: Vec<String>,
+    current_key: usize,
+    current_protocol: usize,
+    cache: Option<Arc<ValidatedCache>>,
+    exclude_transparent: bool,
+    prefer_ipv4: bool,
+    asn_lookup: Option<AsnLookup>,
+    excluded_asns: Vec<u32>,
+    poison_policy: PoisonPolicy,
+    lifetime_cap: Option<usize>,
+    handed_out: usize,
+    largest_batch: usize,
+    stats: Stats,
     proxies: Vec<Proxy>,
+    raw_proxies: Vec<RawProxy>,
+    seen: HashSet<SocketAddr>,
+    transport: SharedTransport,
+    #[cfg(feature = "shuffle")]
+    shuffle_rng: Option<StdRng>,
+    throttle: Arc<Mutex<Throttle>>,
+}
+
+/// Internal
+#[derive(Clone)]
+struct AsnLookup(Arc<dyn Fn(IpAddr) -> Option<u32> + Send + Sync>);
+
+impl fmt::Debug for AsnLookup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("AsnLookup")
+    }
+}
+
+/// Internal
+#[derive(Clone)]
+struct SharedTransport(Arc<dyn Transport>);
+
+impl Default for SharedTransport {
+    fn default() -> Self {
+        // Tests never touch the real API
+        #[cfg(test)]
+        let transport = Arc::new(tests::MockTransport);
+        #[cfg(not(test))]
+        let transport = Arc::new(UreqTransport);
+
+        Self(transport)
+    }
+}
+
+impl fmt::Debug for SharedTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Transport")
+    }
+}
+
+/// How a [`Fetcher`][Fetcher] recovers when the lock coordinating delays between keyless fetchers
+/// was poisoned by a panicking thread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PoisonPolicy {
+    /// Play it safe and reset the timer, so the next request waits out a full delay.
+    #[default]
+    Reset,
+    /// Keep using the last recorded request time as if the lock was never poisoned.
+    Proceed,
+}
+
+/// How [`Fetcher::try_get_with_retry`][Fetcher::try_get_with_retry] retries failed requests.
+///
+/// The delay before each retry doubles starting from `base_backoff`, so the default policy waits
+/// 1, then 2, then 4 seconds between its attempts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The most attempts made in total, including the first.
+    pub max_attempts: usize,
+    /// The delay before the first retry.
+    pub base_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// What [`Fetcher::try_get_sorted`][Fetcher::try_get_sorted] sorts the returned proxies by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    /// Fastest to connect first.
+    TimeToConnect,
+    /// Least recently checked first.
+    LastChecked,
+}
+
+impl SortKey {
+    /// Compares two proxies by this key in ascending order, for use with methods like
+    /// [`slice::sort_by`].
+    ///
+    /// ```
+    /// use lead_oxide::{fetcher::SortKey, proxy::Proxy};
+    ///
+    /// fn fastest_first(proxies: &mut [Proxy]) {
+    ///     proxies.sort_by(|a, b| SortKey::TimeToConnect.compare(a, b));
+    /// }
+    /// ```
+    pub fn compare(self, a: &Proxy, b: &Proxy) -> cmp::Ordering {
+        match self {
+            Self::TimeToConnect => a.time_to_connect.cmp(&b.time_to_connect),
+            Self::LastChecked => a.last_checked.cmp(&b.last_checked),
+        }
+    }
+}
+
+/// A snapshot of a [`Fetcher`][Fetcher]'s state returned by [`Fetcher::health`][Fetcher::health].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    /// The number of proxies in the internal list.
+    pub buffered: usize,
+    /// If the `Fetcher` uses an API key and skips the rate limiting.
+    pub premium: bool,
+    /// The number of requests sent to the API.
+    pub requests_made: usize,
+    /// How many times each kind of [`ApiError`][ApiError] was returned by the API, keyed by the
+    /// variant's name.
+    pub errors_by_kind: HashMap<&'static str, usize>,
+    /// The number of proxies expected from each request. This starts at the API's documented limit
+    /// and grows if the API is seen returning more than that.
+    pub batch_size: usize,
+    /// How long until a request can be made without delaying.
+    pub time_until_ready: Duration,
+    /// The message of the most recent error returned by the API.
+    pub last_error: Option<String>,
+}
+
+/// Internal
+#[derive(Clone, Debug, Default)]
+struct Stats {
+    requests_made: usize,
+    errors_by_kind: HashMap<&'static str, usize>,
+    last_error: Option<String>,
+    // (checked, violated) counts for each filter that was set
+    compliance: HashMap<Filter, (usize, usize)>,
+}
+
+impl Stats {
+    fn record<T>(&mut self, result: &Result<T, ApiError>) {
+        self.requests_made += 1;
+
+        if let Err(err) = result {
+            *self.errors_by_kind.entry(err.kind()).or_insert(0) += 1;
+            self.last_error = Some(err.to_string());
+        }
+    }
+
+    fn record_compliance(&mut self, opts: &Opts, proxies: &[Proxy], now: NaiveDateTime) {
+        for &filter in &Filter::ALL {
+            for proxy in proxies {
+                if let Some(satisfied) = opts.check(filter, proxy, now) {
+                    let (checked, violated) = self.compliance.entry(filter).or_insert((0, 0));
+                    *checked += 1;
+                    *violated += usize::from(!satisfied);
+                }
+            }
+        }
+    }
 }
 
 impl Fetcher {
@@ -51,233 +264,2762 @@ impl Fetcher {
     /// );
     /// ```
     pub fn new(opts: Opts) -> Self {
+        let throttle = if opts.independent_rate_limit {
+            Arc::new(Mutex::new(Throttle::new()))
+        } else {
+            Arc::clone(&THROTTLE)
+        };
+
         Self {
             opts,
+            api_keys: Vec::new(),
+            current_key: 0,
+            current_protocol: 0,
+            cache: None,
+            exclude_transparent: false,
+            prefer_ipv4: false,
+            asn_lookup: None,
+            excluded_asns: Vec::new(),
+            poison_policy: PoisonPolicy::default(),
+            lifetime_cap: None,
+            handed_out: 0,
+            largest_batch: 0,
+            stats: Stats::default(),
             proxies: Vec::new(),
+            raw_proxies: Vec::new(),
+            seen: HashSet::new(),
+            transport: SharedTransport::default(),
+            #[cfg(feature = "shuffle")]
+            shuffle_rng: None,
+            throttle,
+        }
+    }
+
+    /// Rotates between several API keys instead of the single key set on the `Opts`. Keys are
+    /// used round-robin across requests and a key that gets rejected with `ApiError::ApiKey` or
+    /// `ApiError::DailyLimit` is skipped in favor of the next one. An error is only returned once
+    /// every key has been rejected in a row. Any key provided through the `Opts` is ignored while
+    /// rotating. The fetcher is considered premium as long as at least one key is present.
+    ///
+    /// ```
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let fetcher = Fetcher::default()
+    ///     .with_api_keys(vec!["<key1>".to_string(), "<key2>".to_string()]);
+    /// ```
+    pub fn with_api_keys(mut self, api_keys: Vec<String>) -> Self {
+        self.api_keys = api_keys;
+        self.current_key = 0;
+        self
+    }
+
+    /// Shares a [`ValidatedCache`][ValidatedCache] with this `Fetcher`. Any unexpired proxies in
+    /// the cache are handed out before falling back to the internal list and then the API.
+    pub fn with_cache(mut self, cache: Arc<ValidatedCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Drops any [`Level::Transparent`][Level::Transparent] proxies returned by the API. The API
+    /// can't filter these out for us, so unfiltered requests can contain them. This is ignored
+    /// with the [`txt` format][crate::opts::OptsBuilder::format_txt] since it doesn't include
+    /// the level of each proxy.
+    pub fn exclude_transparent(mut self, exclude: bool) -> Self {
+        self.exclude_transparent = exclude;
+        self
+    }
+
+    /// Hands out IPv4 proxies before IPv6 ones for networks that can't always route IPv6. IPv6
+    /// proxies are still returned when there aren't enough IPv4 proxies in the internal list to
+    /// fulfill the request, after all of the IPv4 ones.
+    ///
+    /// ```
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let fetcher = Fetcher::default().prefer_ipv4(true);
+    /// ```
+    pub fn prefer_ipv4(mut self, prefer: bool) -> Self {
+        self.prefer_ipv4 = prefer;
+        self
+    }
+
+    /// Provides a way to look up the ASN (autonomous system number) for a proxy's IP. The API
+    /// doesn't know anything about ASNs, so this is how [`exclude_asns`][Fetcher::exclude_asns]
+    /// is able to drop proxies from specific hosting providers. `lookup` should return `None` when
+    /// the ASN isn't known, which never gets excluded.
+    ///
+    /// ```
+    /// use lead_oxide::fetcher::Fetcher;
+    /// use std::net::IpAddr;
+    ///
+    /// // Typically this would query something like a local ASN database
+    /// fn lookup(ip: IpAddr) -> Option<u32> {
+    ///     None
+    /// }
+    ///
+    /// let fetcher = Fetcher::default()
+    ///     .with_asn_lookup(lookup)
+    ///     .exclude_asns(&[16509, 14618]);
+    /// ```
+    pub fn with_asn_lookup<F>(mut self, lookup: F) -> Self
+    where
+        F: Fn(IpAddr) -> Option<u32> + Send + Sync + 'static,
+    {
+        self.asn_lookup = Some(AsnLookup(Arc::new(lookup)));
+        self
+    }
+
+    /// Drops any fetched proxies whose ASN is one of `asns`. This does nothing without an ASN
+    /// lookup provided through [`with_asn_lookup`][Fetcher::with_asn_lookup].
+    pub fn exclude_asns(mut self, asns: &[u32]) -> Self {
+        self.excluded_asns = asns.to_vec();
+        self
+    }
+
+    /// Sends requests through `transport` instead of the default
+    /// [`UreqTransport`][transport::UreqTransport]. See [`Transport`][Transport] for an example.
+    pub fn with_transport<T>(mut self, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        self.transport = SharedTransport(Arc::new(transport));
+        self
+    }
+
+    /// Sets how a poisoned delay lock is recovered from, see [`PoisonPolicy`][PoisonPolicy]. The
+    /// default is `PoisonPolicy::Reset`.
+    pub fn poison_policy(mut self, policy: PoisonPolicy) -> Self {
+        self.poison_policy = policy;
+        self
+    }
+
+    /// Caps the total number of proxies this `Fetcher` will ever return at `cap`. Once the cap is
+    /// reached every method returns fewer proxies than requested, down to none at all, without
+    /// making any more requests. This bounds the number of proxies handed out rather than the
+    /// number of requests made, and it includes raw proxies and anything returned by
+    /// [`drain`][Fetcher::drain].
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default().with_lifetime_cap(3);
+    /// assert_eq!(fetcher.try_get(2).unwrap().len(), 2);
+    /// assert_eq!(fetcher.try_get(2).unwrap().len(), 1);
+    /// assert!(fetcher.try_get(2).unwrap().is_empty());
+    /// ```
+    pub fn with_lifetime_cap(mut self, cap: usize) -> Self {
+        self.lifetime_cap = Some(cap);
+        self
+    }
+
+    /// Seeds the order used by [`try_get_shuffled`][Fetcher::try_get_shuffled] so that the same
+    /// proxies always get shuffled the same way, which is handy for reproducible tests. Without a
+    /// seed the order is different every run.
+    ///
+    /// This is only available with the `shuffle` feature enabled.
+    #[cfg(feature = "shuffle")]
+    pub fn with_shuffle_seed(mut self, seed: u64) -> Self {
+        self.shuffle_rng = Some(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Splits the internal list into proxies that are known to be good and ones that haven't been
+    /// validated yet, leaving the internal list untouched. A proxy is known to be good when an
+    /// unexpired entry for it is in the `Fetcher`'s [`ValidatedCache`][ValidatedCache], so
+    /// everything is unvalidated when there isn't a cache.
+    ///
+    /// ```no_run
+    /// use lead_oxide::{cache::ValidatedCache, fetcher::Fetcher};
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// let cache = Arc::new(ValidatedCache::new(Duration::from_secs(60 * 60)));
+    /// let mut fetcher = Fetcher::default().with_cache(Arc::clone(&cache));
+    /// let _ = fetcher.try_get(1).unwrap();
+    /// let (known_good, unvalidated) = fetcher.partition_by_health();
+    /// ```
+    pub fn partition_by_health(&self) -> (Vec<Proxy>, Vec<Proxy>) {
+        match &self.cache {
+            Some(cache) => self
+                .proxies
+                .iter()
+                .cloned()
+                .partition(|proxy| cache.contains(proxy)),
+            None => (Vec::new(), self.proxies.clone()),
+        }
+    }
+
+    /// Attempts to get the specified amount of proxies from the API, returning an error if there
+    /// was an issue with the API. Any proxies returned before an error was encountered will still
+    /// be stored in the interal buffer, so it's possible to use this to fully exhaust your daily
+    /// limit. This also means that retrying after an error only needs to request the remainder,
+    /// since the proxies from the requests that succeeded get used first.
+    ///
+    /// When several requests in a row have nothing left after the filtering done on this side (like
+    /// [deduping][crate::opts::OptsBuilder::dedupe]) this gives up with `ApiError::NoProxy`
+    /// instead of requesting forever.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// // Exhaust the daily limit
+    /// assert!(fetcher.try_get(1_000).is_err());
+    /// // Drain the full list
+    /// let proxies = fetcher.drain();
+    /// ```
+    pub fn try_get(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        let amount = amount.min(self.remaining());
+
+        // Validated proxies from a shared cache are always preferred as long as they would have
+        // been kept if this `Fetcher` fetched them itself
+        let mut proxies = match &self.cache {
+            Some(cache) => cache.take_matching(amount, |proxy| self.accepts_cached(proxy)),
+            None => Vec::new(),
+        };
+        if self.opts.dedupe {
+            self.seen.extend(proxies.iter().map(|proxy| proxy.socket));
+        }
+
+        match self.take_or_fetch(amount - proxies.len()) {
+            Ok(mut fetched) => {
+                proxies.append(&mut fetched);
+                Ok(self.hand_out(proxies))
+            }
+            Err(err) => {
+                // Don't lose the cached proxies if we couldn't fulfill the request
+                if let Some(cache) = &self.cache {
+                    for proxy in proxies {
+                        cache.insert(proxy);
+                    }
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Like [`try_get`][Fetcher::try_get], but gets a single proxy. This returns
+    /// `ApiError::NoProxy` when no proxy could be returned, which only happens once the
+    /// [lifetime cap][Fetcher::with_lifetime_cap] is reached.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let proxy = fetcher.try_get_one().unwrap();
+    /// println!("Using {}", proxy);
+    /// ```
+    pub fn try_get_one(&mut self) -> Result<Proxy, ApiError> {
+        self.try_get(1)?
+            .pop()
+            .ok_or(ApiError::NoProxy { response: None })
+    }
+
+    /// Like [`try_get`][Fetcher::try_get], but retries with an exponential backoff when the API
+    /// returns `ApiError::RateLimit` or `ApiError::Server`, or can't be reached at all with
+    /// `ApiError::Transport`, following `policy`. The usual delays between requests are still
+    /// respected on top of the backoff. Any other error is returned right away, and the last error
+    /// is returned if every attempt failed.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::{Fetcher, RetryPolicy};
+    /// use std::time::Duration;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let policy = RetryPolicy {
+    ///     max_attempts: 3,
+    ///     base_backoff: Duration::from_secs(30),
+    /// };
+    /// let proxies = fetcher.try_get_with_retry(10, policy).unwrap();
+    /// ```
+    pub fn try_get_with_retry(
+        &mut self,
+        amount: usize,
+        policy: RetryPolicy,
+    ) -> Result<Vec<Proxy>, ApiError> {
+        let mut backoff = policy.base_backoff;
+        let mut attempts = 1;
+        loop {
+            match self.try_get(amount) {
+                Err(err) if err.is_retryable() && attempts < policy.max_attempts => {
+                    debug!(
+                        "Retrying in {:?} after attempt {} of {} failed: {}",
+                        backoff, attempts, policy.max_attempts, err
+                    );
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                    attempts += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Like [`try_get`][Fetcher::try_get], but automatically loosens the `Opts` when the API
+    /// returns `ApiError::NoProxy`. Filters are dropped one at a time in the order given by
+    /// `relaxation_order` (skipping any that aren't set) until a request succeeds. The returned
+    /// proxies are paired with the filters that had to be dropped to get them. The `Fetcher`'s own
+    /// `Opts` are restored afterwards, but note that any extra proxies stored in the internal list
+    /// from a relaxed request won't match the original filters.
+    ///
+    /// ```no_run
+    /// use lead_oxide::{fetcher::Fetcher, opts::{Filter, Opts}, types::Level};
+    ///
+    /// let mut fetcher = Fetcher::new(Opts::builder().level(Level::Elite).post(true).build());
+    /// let (proxies, dropped) = fetcher
+    ///     .try_get_adaptive(5, &[Filter::Post, Filter::Level])
+    ///     .unwrap();
+    /// println!("Had to drop {:?} to get {} proxies", dropped, proxies.len());
+    /// ```
+    pub fn try_get_adaptive(
+        &mut self,
+        amount: usize,
+        relaxation_order: &[Filter],
+    ) -> Result<(Vec<Proxy>, Vec<Filter>), ApiError> {
+        let original = self.opts.clone();
+        let mut relaxed = Vec::new();
+        let mut to_relax = relaxation_order.iter();
+
+        let result = loop {
+            match self.try_get(amount) {
+                Err(err @ ApiError::NoProxy { .. }) => {
+                    let opts = &mut self.opts;
+                    match to_relax.by_ref().find(|&&filter| opts.relax(filter)) {
+                        Some(&filter) => relaxed.push(filter),
+                        None => break Err(err),
+                    }
+                }
+                result => break result,
+            }
+        };
+
+        self.opts = original;
+        result.map(|proxies| (proxies, relaxed))
+    }
+
+    /// Keeps fetching proxies until `pred` over all of the accumulated proxies returns `true`, then
+    /// returns everything that was accumulated. This generalizes `try_get` to stopping conditions
+    /// other than a count, like having enough proxies from each of several countries. Delays are
+    /// still respected between requests and any error from the API is returned as is, leaving the
+    /// proxies accumulated so far in the internal list.
+    ///
+    /// ```no_run
+    /// use iso_country::Country;
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// // Get at least 2 proxies from both the US and Canada
+    /// let proxies = fetcher
+    ///     .try_get_until_satisfied(|proxies| {
+    ///         [Country::US, Country::CA].iter().all(|&country| {
+    ///             proxies.iter().filter(|proxy| proxy.country == country).count() >= 2
+    ///         })
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn try_get_until_satisfied<F>(&mut self, pred: F) -> Result<Vec<Proxy>, ApiError>
+    where
+        F: Fn(&[Proxy]) -> bool,
+    {
+        if self.remaining() == 0 {
+            return Ok(Vec::new());
+        }
+
+        self.fill_proxies_until(pred)?;
+        let proxies = mem::take(&mut self.proxies);
+        Ok(self.hand_out(proxies))
+    }
+
+    /// Like [`try_get`][Fetcher::try_get], but returns the `amount` most recently checked proxies
+    /// from the internal list, sorted newest first. This doesn't pull from the `Fetcher`'s
+    /// [`ValidatedCache`][ValidatedCache] since the API's `last_checked` is what's being compared.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let proxies = fetcher.try_get_freshest(3).unwrap();
+    /// assert!(proxies[0].last_checked >= proxies[1].last_checked);
+    /// ```
+    pub fn try_get_freshest(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        let amount = amount.min(self.remaining());
+        self.fill_proxies_until(|proxies| proxies.len() >= amount)?;
+
+        // Oldest first so that the freshest are at the tail for `split_off`
+        self.proxies.sort_by_key(|proxy| proxy.last_checked);
+        let mut freshest = self.proxies.split_off(self.proxies.len() - amount);
+        freshest.reverse();
+        Ok(self.hand_out(freshest))
+    }
+
+    /// Like [`try_get`][Fetcher::try_get], but the returned proxies are sorted in ascending order
+    /// `by` the [`SortKey`][SortKey]. Only the returned proxies are sorted, so unlike
+    /// [`try_get_freshest`][Fetcher::try_get_freshest] these aren't the best proxies in the
+    /// internal list, just the same ones `try_get` would return.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::{Fetcher, SortKey};
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let proxies = fetcher.try_get_sorted(3, SortKey::TimeToConnect).unwrap();
+    /// assert!(proxies[0].time_to_connect <= proxies[1].time_to_connect);
+    /// ```
+    pub fn try_get_sorted(&mut self, amount: usize, by: SortKey) -> Result<Vec<Proxy>, ApiError> {
+        let mut proxies = self.try_get(amount)?;
+        proxies.sort_by(|a, b| by.compare(a, b));
+        Ok(proxies)
+    }
+
+    /// Like [`try_get`][Fetcher::try_get], but the returned proxies are in a random order. The API
+    /// tends to return proxies in the same order, so shuffling avoids always using the same proxy
+    /// first across runs. Only the returned proxies are shuffled, not the internal list. See
+    /// [`with_shuffle_seed`][Fetcher::with_shuffle_seed] for a reproducible order.
+    ///
+    /// This is only available with the `shuffle` feature enabled.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let proxies = fetcher.try_get_shuffled(5).unwrap();
+    /// ```
+    #[cfg(feature = "shuffle")]
+    pub fn try_get_shuffled(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        let mut proxies = self.try_get(amount)?;
+        proxies.shuffle(self.shuffle_rng.get_or_insert_with(StdRng::from_entropy));
+        Ok(proxies)
+    }
+
+    /// Gets proxies exactly as they were returned by the API. This skips all of the parsing and
+    /// validation done to create a [`Proxy`][Proxy], so no proxies are filtered out and fields that
+    /// `Proxy` doesn't use are kept. Raw proxies have their own internal list separate from the one
+    /// used by [`try_get`][Fetcher::try_get], but delays are coordinated the same.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// for raw in fetcher.try_get_raw_proxies(5).unwrap() {
+    ///     println!("{} was last checked at {}", raw.ip_port, raw.last_checked);
+    /// }
+    /// ```
+    pub fn try_get_raw_proxies(&mut self, amount: usize) -> Result<Vec<RawProxy>, ApiError> {
+        let amount = amount.min(self.remaining());
+        self.fill_until(
+            |fetcher| fetcher.raw_proxies.len() >= amount,
+            |fetcher| {
+                // Raw proxies only exist in the json format
+                let opts = fetcher
+                    .opts
+                    .next_request(&mut fetcher.current_protocol)
+                    .with_format(Format::Json);
+                let mut raw_proxies = fetcher.fetch_parsed(&opts, raw_proxies_from_json)?;
+                let fetched = raw_proxies.len();
+                fetcher.raw_proxies.append(&mut raw_proxies);
+                Ok(fetched)
+            },
+        )?;
+        self.handed_out += amount;
+        Ok(self.raw_proxies.split_off(self.raw_proxies.len() - amount))
+    }
+
+    fn take_or_fetch(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        // Plan around the batches the API actually returns, so larger ones need fewer requests
+        let missing = amount.saturating_sub(self.proxies.len());
+        if missing > 0 {
+            debug!(
+                "Expecting {} request(s) for {} more proxies",
+                missing.div_ceil(self.batch_size()),
+                missing
+            );
+        }
+
+        self.fill_proxies_until(|proxies| proxies.len() >= amount)?;
+        if self.prefer_ipv4 {
+            // Proxies are taken from the end, so that's where the IPv4 ones go
+            self.proxies.sort_by_key(|proxy| proxy.socket.is_ipv4());
+        }
+        let mut proxies = self.proxies.split_off(self.proxies.len() - amount);
+        if self.prefer_ipv4 {
+            proxies.sort_by_key(|proxy| proxy.socket.is_ipv6());
+        }
+
+        Ok(proxies)
+    }
+
+    fn fill_proxies_until<F>(&mut self, done: F) -> Result<(), ApiError>
+    where
+        F: Fn(&[Proxy]) -> bool,
+    {
+        self.fill_until(|fetcher| done(&fetcher.proxies), Self::fetch_and_store)
+    }
+
+    // `fetch` returns how many new entries it stored
+    fn fill_until<D, F>(&mut self, done: D, mut fetch: F) -> Result<(), ApiError>
+    where
+        D: Fn(&Self) -> bool,
+        F: FnMut(&mut Self) -> Result<usize, ApiError>,
+    {
+        if done(self) {
+            // If there's enough in the current list then we're already done without locking
+            return Ok(());
+        }
+
+        // Give up once too many requests in a row are entirely filtered out instead of requesting
+        // forever
+        let mut empty_fetches = 0;
+        let mut fetch = |fetcher: &mut Self| {
+            if fetch(fetcher)? == 0 {
+                empty_fetches += 1;
+                if empty_fetches >= constants::MAX_EMPTY_FETCHES {
+                    debug!(
+                        "Giving up after {} requests in a row had nothing usable",
+                        empty_fetches
+                    );
+                    return Err(ApiError::NoProxy { response: None });
+                }
+            } else {
+                empty_fetches = 0;
+            }
+
+            Ok(())
+        };
+
+        // Otherwise we need to lock and request the api
+        if self.is_premium() {
+            // Don't need to mess with any delays if we're using an api key. (This information was
+            // based off emailing the dev. I never got an api key to test)
+            while !done(self) {
+                fetch(self)?;
+            }
+        } else {
+            // If we don't have an api key then we need to coordinate delays to ensure we don't do
+            // more than one request per delay
+            let shared = Arc::clone(&self.throttle);
+            let mut throttle = match shared.lock() {
+                Ok(throttle) => throttle,
+                Err(err) => {
+                    // The timer is still a valid `Instant` so it's up to the policy whether to
+                    // trust it. Either way the poison is cleared so recovery only happens once
+                    let mut poisoned = err.into_inner();
+                    if self.poison_policy == PoisonPolicy::Reset {
+                        poisoned.last_fetched = Instant::now();
+                    }
+                    shared.clear_poison();
+                    poisoned
+                }
+            };
+            while !done(self) {
+                // Delay to prevent rate limiting
+                let delay = throttle.delay_for(&self.opts);
+                let delta = Instant::now().duration_since(throttle.last_fetched);
+                if delta < delay {
+                    trace!("Waiting {:?} to avoid the rate limit", delay - delta);
+                    thread::sleep(delay - delta);
+                }
+
+                fetch(self)?;
+
+                // Update the request time
+                throttle.fetched(&self.opts);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a snapshot of the `Fetcher`'s current state for diagnostics. No requests are made
+    /// and the rate limiting timer isn't touched.
+    ///
+    /// ```
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let report = Fetcher::default().health();
+    /// assert_eq!(report.buffered, 0);
+    /// assert_eq!(report.requests_made, 0);
+    /// ```
+    pub fn health(&self) -> HealthReport {
+        HealthReport {
+            buffered: self.proxies.len(),
+            premium: self.is_premium(),
+            requests_made: self.stats.requests_made,
+            errors_by_kind: self.stats.errors_by_kind.clone(),
+            batch_size: self.batch_size(),
+            time_until_ready: self.time_until_ready(),
+            last_error: self.stats.last_error.clone(),
         }
     }
 
-    /// Attempts to get the specified amount of proxies from the API, returning an error if there
-    /// was an issue with the API. Any proxies returned before an error was encountered will still
-    /// be stored in the interal buffer, so it's possible to use this to fully exhaust your daily
-    /// limit.
-    ///
-    /// ```no_run
-    /// use lead_oxide::fetcher::Fetcher;
-    ///
-    /// let mut fetcher = Fetcher::default();
-    /// // Exhaust the daily limit
-    /// assert!(fetcher.try_get(1_000).is_err());
-    /// // Drain the full list
-    /// let proxies = fetcher.drain();
-    /// ```
-    pub fn try_get(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
-        if self.proxies.len() >= amount {
-            // If there's enough in the current list then just go ahead and fulfill without locking
-            Ok(self.proxies.split_off(self.proxies.len() - amount))
-        } else {
-            // Otherwise we need to lock and request the api
-            let mut request = self.request_builder();
-
-            if self.opts.is_premium() {
-                // Don't need to mess with any delays if we're using an api key. (This information
-                // was based off emailing the dev. I never got an api key to test)
-                while self.proxies.len() < amount {
-                    let mut proxies = self.fetch(&mut request)?;
-                    self.proxies.append(&mut proxies);
-                }
-            } else {
-                // If we don't have an api key then we need to coordinate delays to ensure we don't
-                // do more than one request per `constants::DELAY`
-                let mut last_fetched = match LAST_FETCHED.lock() {
-                    Ok(last_fetched) => last_fetched,
-                    Err(err) => {
-                        // If the lock was poisoned then play it safe and reset the timer
-                        let mut poisioned = err.into_inner();
-                        *poisioned = Instant::now();
-                        poisioned
-                    }
-                };
+    // The API's limits aren't guaranteed so trust what it actually returns if that's more
+    fn batch_size(&self) -> usize {
+        let limit = if self.is_premium() {
+            Limit::Premium
+        } else {
+            Limit::Free
+        };
+
+        self.largest_batch.max(limit as usize)
+    }
+
+    fn remaining(&self) -> usize {
+        match self.lifetime_cap {
+            Some(cap) => cap.saturating_sub(self.handed_out),
+            None => usize::MAX,
+        }
+    }
+
+    // Anything past the lifetime cap goes back to the internal list
+    fn hand_out(&mut self, mut proxies: Vec<Proxy>) -> Vec<Proxy> {
+        let remaining = self.remaining();
+        if proxies.len() > remaining {
+            let mut excess = proxies.split_off(remaining);
+            self.proxies.append(&mut excess);
+        }
+
+        self.handed_out += proxies.len();
+        proxies
+    }
+
+    // Undoes `hand_out` for proxies that never made it to the caller
+    fn give_back(&mut self, mut proxies: Vec<Proxy>) {
+        self.handed_out = self.handed_out.saturating_sub(proxies.len());
+        self.proxies.append(&mut proxies);
+    }
+
+    /// Reports how often the API seems to ignore each of the requested filters. Every proxy that
+    /// gets fetched is checked against the `Fetcher`'s `Opts` (see
+    /// [`Proxy::satisfies`][Proxy::satisfies]) and this returns the fraction of proxies that
+    /// violated each filter that's set, from `0.0` for a filter that was always respected up to
+    /// `1.0` for one that was always ignored. Filters that aren't set or haven't been checked
+    /// against any proxies yet are left out.
+    ///
+    /// ```no_run
+    /// use lead_oxide::{fetcher::Fetcher, opts::{Filter, Opts}};
+    ///
+    /// let mut fetcher = Fetcher::new(Opts::builder().post(true).build());
+    /// let _ = fetcher.try_get(10);
+    /// if let Some(ignored) = fetcher.filter_compliance().get(&Filter::Post) {
+    ///     println!("The API ignored the post filter {:.0}% of the time", ignored * 100.0);
+    /// }
+    /// ```
+    pub fn filter_compliance(&self) -> HashMap<Filter, f32> {
+        self.stats
+            .compliance
+            .iter()
+            .map(|(&filter, &(checked, violated))| (filter, violated as f32 / checked as f32))
+            .collect()
+    }
+
+    fn is_premium(&self) -> bool {
+        !self.api_keys.is_empty() || self.opts.is_premium()
+    }
+
+    /// How long until the next request from this `Fetcher` can be made without waiting on the
+    /// delay between keyless requests. This is always zero for premium fetchers and is handy for
+    /// scheduling other work instead of blocking in [`try_get`][Fetcher::try_get]. Checking
+    /// doesn't make a request or affect the delay.
+    ///
+    /// ```
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let fetcher = Fetcher::default();
+    /// if fetcher.time_until_ready().as_secs() == 0 {
+    ///     println!("Can fetch right away");
+    /// }
+    /// ```
+    pub fn time_until_ready(&self) -> Duration {
+        if self.is_premium() {
+            Duration::from_secs(0)
+        } else {
+            let (last_fetched, delay) = match self.throttle.lock() {
+                Ok(throttle) => (throttle.last_fetched, throttle.delay_for(&self.opts)),
+                Err(err) => {
+                    let throttle = err.into_inner();
+                    (throttle.last_fetched, throttle.delay_for(&self.opts))
+                }
+            };
+
+            delay.saturating_sub(Instant::now().duration_since(last_fetched))
+        }
+    }
+
+    fn request_builder(opts: &Opts) -> ureq::Request {
+        let mut request = ureq::get(opts.api_base());
+        request
+            .query_str(&query_params(opts))
+            .set("User-Agent", opts.user_agent());
+        if let Some(timeout) = opts.request_timeout {
+            set_timeouts(&mut request, timeout);
+        }
+
+        request.build()
+    }
+
+    // The same filtering that's done in `fetch_and_store`, but for proxies from the cache
+    fn accepts_cached(&self, proxy: &Proxy) -> bool {
+        let transparent = self.excludes_transparent() && proxy.level == Level::Transparent;
+        let duplicate = self.opts.dedupe && self.seen.contains(&proxy.socket);
+        proxy.satisfies(&self.opts) && !transparent && !duplicate && !self.has_excluded_asn(proxy)
+    }
+
+    // Every proxy from the `txt` format is marked transparent unless the level was filtered on, so
+    // excluding them would throw away everything
+    fn excludes_transparent(&self) -> bool {
+        self.exclude_transparent && self.opts.format != Format::Txt
+    }
+
+    fn has_excluded_asn(&self, proxy: &Proxy) -> bool {
+        match &self.asn_lookup {
+            Some(AsnLookup(lookup)) if !self.excluded_asns.is_empty() => {
+                match lookup(proxy.socket.ip()) {
+                    Some(asn) => self.excluded_asns.contains(&asn),
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn fetch_and_store(&mut self) -> Result<usize, ApiError> {
+        let mut proxies = self.fetch()?;
+        let now = chrono::Utc::now().naive_utc();
+        self.stats.record_compliance(&self.opts, &proxies, now);
+
+        // Apply any filtering that the API can't do for us
+        let returned = proxies.len();
+        let kept = |keep: bool, proxy: &Proxy, reason: &str| {
+            if !keep {
+                trace!("Dropping {} for {}", proxy, reason);
+            }
+            keep
+        };
+        proxies.retain(|proxy| kept(self.opts.allows(proxy, now), proxy, "not matching the opts"));
+        if self.excludes_transparent() {
+            proxies.retain(|proxy| {
+                kept(
+                    proxy.level != Level::Transparent,
+                    proxy,
+                    "being transparent",
+                )
+            });
+        }
+        if self.opts.dedupe {
+            let seen = &mut self.seen;
+            proxies.retain(|proxy| kept(seen.insert(proxy.socket), proxy, "being a duplicate"));
+        }
+        proxies.retain(|proxy| {
+            kept(
+                !self.has_excluded_asn(proxy),
+                proxy,
+                "having an excluded ASN",
+            )
+        });
+
+        let stored = proxies.len();
+        if stored < returned {
+            debug!("Kept {} of the {} returned proxies", stored, returned);
+        }
+        self.proxies.append(&mut proxies);
+        Ok(stored)
+    }
+
+    fn fetch(&mut self) -> Result<Vec<Proxy>, ApiError> {
+        let opts = self.opts.next_request(&mut self.current_protocol);
+        match opts.format {
+            Format::Json => {
+                let keep_unknown = opts.keep_unknown_countries;
+                self.fetch_parsed(&opts, |json| proxies_from_json(json, keep_unknown))
+            }
+            Format::Txt => {
+                let mut proxies = self.fetch_parsed(&opts, proxies_from_txt)?;
+                for proxy in &mut proxies {
+                    opts.fill_known(proxy);
+                }
+                Ok(proxies)
+            }
+        }
+    }
+
+    fn fetch_parsed<T, E, P>(&mut self, base: &Opts, parse: P) -> Result<Vec<T>, ApiError>
+    where
+        P: Fn(&str) -> Result<Vec<T>, E>,
+        E: ParseFailure,
+    {
+        if self.api_keys.is_empty() {
+            let result = self.fetch_with(base, &parse);
+            self.record(&result);
+            return result;
+        }
+
+        // Rotate through the keys, only giving up once every key was rejected
+        let mut rejected = 0;
+        loop {
+            let opts = base.with_api_key(self.api_keys[self.current_key].clone());
+            self.current_key = (self.current_key + 1) % self.api_keys.len();
+
+            let result = self.fetch_with(&opts, &parse);
+            self.record(&result);
+            match result {
+                Err(ApiError::ApiKey { .. }) | Err(ApiError::DailyLimit { .. })
+                    if rejected + 1 < self.api_keys.len() =>
+                {
+                    rejected += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn record<T>(&mut self, result: &Result<Vec<T>, ApiError>) {
+        self.stats.record(result);
+        if let Ok(batch) = result {
+            self.largest_batch = self.largest_batch.max(batch.len());
+        }
+    }
+
+    fn fetch_with<T, E, P>(&self, opts: &Opts, parse: P) -> Result<Vec<T>, ApiError>
+    where
+        P: Fn(&str) -> Result<Vec<T>, E>,
+        E: ParseFailure,
+    {
+        let SharedTransport(transport) = &self.transport;
+        debug!("Requesting proxies with `{}`", redacted_query(opts));
+        let result = transport
+            .call(Self::request_builder(opts))
+            .and_then(|naive_resp| parse_response(naive_resp, parse));
+        log_outcome(&result);
+
+        result
+    }
+
+    /// Moves every proxy in the internal list into `sender`, returning how many were sent. Sending
+    /// blocks while the channel is full, so a bounded channel naturally limits how far ahead of the
+    /// consumers this gets. If the receiving side hung up then any unsent proxies are kept in the
+    /// internal list.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    /// use std::sync::mpsc;
+    ///
+    /// let (sender, receiver) = mpsc::sync_channel(10);
+    /// let mut fetcher = Fetcher::default();
+    /// // Hand off the extra proxies left over from getting one
+    /// let proxy = fetcher.try_get(1).unwrap();
+    /// let sent = fetcher.drain_into(&sender);
+    /// ```
+    pub fn drain_into(&mut self, sender: &SyncSender<Proxy>) -> usize {
+        let buffered = mem::take(&mut self.proxies);
+        let to_send = self.hand_out(buffered);
+        let total = to_send.len();
+
+        let mut to_send = to_send.into_iter();
+        let mut sent = 0;
+        while let Some(proxy) = to_send.next() {
+            if let Err(SendError(proxy)) = sender.send(proxy) {
+                // Nobody is listening, so none of these were actually handed out
+                let mut unsent = vec![proxy];
+                unsent.extend(to_send);
+                unsent.append(&mut self.proxies);
+                self.proxies = unsent;
+                self.handed_out -= total - sent;
+                break;
+            }
+
+            sent += 1;
+        }
+
+        sent
+    }
+
+    /// Moves the `Fetcher` to a background thread that keeps fetching proxies and sending them
+    /// into `sender`. Delays are still coordinated with every other keyless `Fetcher` and the
+    /// delay isn't held up while waiting on a full channel. The thread stops once the receiving
+    /// side hangs up or the [lifetime cap][Fetcher::with_lifetime_cap] is reached, and any error
+    /// from the API is returned from the thread.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    /// use std::sync::mpsc;
+    ///
+    /// let (sender, receiver) = mpsc::sync_channel(10);
+    /// let feeder = Fetcher::default().with_lifetime_cap(50).spawn_feeder(sender);
+    /// for proxy in receiver {
+    ///     println!("Got {}", proxy.socket);
+    /// }
+    /// feeder.join().unwrap().unwrap();
+    /// ```
+    pub fn spawn_feeder(mut self, sender: SyncSender<Proxy>) -> JoinHandle<Result<(), ApiError>> {
+        thread::spawn(move || loop {
+            self.drain_into(&sender);
+
+            // Anything left over means the receiver hung up
+            if self.remaining() == 0 || !self.proxies.is_empty() {
+                return Ok(());
+            }
+
+            self.fill_proxies_until(|proxies| !proxies.is_empty())?;
+        })
+    }
+
+    /// Moves the `Fetcher` to a background thread that keeps the internal list topped up to
+    /// `target` proxies. Requests are made as soon as the delay allows, so proxies can be taken
+    /// from the returned [`PrefetchHandle`][PrefetchHandle] without waiting on the delay as long
+    /// as the buffer keeps up.
+    ///
+    /// Any error from prefetching is returned from the next
+    /// [`PrefetchHandle::try_get`][PrefetchHandle::try_get]. Prefetching picks back up after
+    /// errors that clear up on their own like `ApiError::RateLimit`, while any other error (like
+    /// `ApiError::DailyLimit`) stops the background thread and the handle falls back to fetching
+    /// like a normal `Fetcher`.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut prefetcher = Fetcher::default().spawn_prefetcher(20);
+    /// // ... do some other work while the buffer fills up
+    /// let proxies = prefetcher.try_get(10).unwrap();
+    /// let fetcher = prefetcher.shutdown();
+    /// ```
+    pub fn spawn_prefetcher(self, target: usize) -> PrefetchHandle {
+        let (requests, receiver) = mpsc::channel();
+        let worker = thread::spawn(move || self.prefetch(target, receiver));
+
+        PrefetchHandle {
+            requests: Some(requests),
+            worker: Some(worker),
+            fetcher: None,
+        }
+    }
+
+    fn prefetch(mut self, target: usize, requests: mpsc::Receiver<PrefetchRequest>) -> Self {
+        let mut error = None;
+        loop {
+            let wants_more = error.is_none() && self.proxies.len() < target.min(self.remaining());
+            let request = if wants_more {
+                match requests.recv_timeout(self.time_until_ready()) {
+                    Ok(request) => Some(request),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match requests.recv() {
+                    Ok(request) => Some(request),
+                    Err(_) => break,
+                }
+            };
+
+            match request {
+                Some(PrefetchRequest { amount, reply }) => {
+                    let result = match error.take() {
+                        Some(err) => Err(err),
+                        None => self.try_get(amount),
+                    };
+                    let fatal = matches!(&result, Err(err) if !err.is_retryable());
+                    let _ = reply.send(result);
+
+                    if fatal {
+                        break;
+                    }
+                }
+                None => {
+                    let buffered = self.proxies.len();
+                    if let Err(err) = self.fill_proxies_until(|proxies| proxies.len() > buffered) {
+                        error = Some(err);
+                    }
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Returns the proxies currently in the internal list without removing them. This never makes a
+    /// request to the API.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let _ = fetcher.try_get(1).unwrap();
+    /// for proxy in fetcher.peek() {
+    ///     println!("Still have {}", proxy.socket);
+    /// }
+    /// ```
+    pub fn peek(&self) -> &[Proxy] {
+        &self.proxies
+    }
+
+    /// The number of proxies in the internal list. A call to `try_get` for at most this many
+    /// proxies won't make any requests to the API.
+    pub fn buffered(&self) -> usize {
+        self.proxies.len()
+    }
+
+    /// Drops every proxy in the internal list, returning how many were dropped. Unlike
+    /// [`drain`][Fetcher::drain] this keeps the `Fetcher` around for more requests.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let _ = fetcher.try_get(1).unwrap();
+    /// fetcher.clear();
+    /// assert_eq!(fetcher.buffered(), 0);
+    /// ```
+    pub fn clear(&mut self) -> usize {
+        let cleared = self.proxies.len();
+        self.proxies.clear();
+        cleared
+    }
+
+    /// Takes every proxy in the internal list without making any requests. Unlike
+    /// [`drain`][Fetcher::drain] this keeps the `Fetcher` around for more requests, and unlike
+    /// [`clear`][Fetcher::clear] the proxies are returned instead of dropped. These still count
+    /// towards the [lifetime cap][Fetcher::with_lifetime_cap].
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let mut proxies = fetcher.try_get(1).unwrap();
+    /// proxies.append(&mut fetcher.take_buffered());
+    /// assert_eq!(fetcher.buffered(), 0);
+    /// ```
+    pub fn take_buffered(&mut self) -> Vec<Proxy> {
+        let buffered = mem::take(&mut self.proxies);
+        self.hand_out(buffered)
+    }
+
+    /// Like [`try_get`][Fetcher::try_get], but only ever uses the proxies already in the internal
+    /// list. This returns `None` instead of making a request whenever there aren't enough
+    /// buffered, so it never blocks on the API or the delay between requests.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// match fetcher.try_get_buffered(2) {
+    ///     Some(proxies) => println!("Got {} right away", proxies.len()),
+    ///     None => println!("Nothing buffered yet"),
+    /// }
+    /// ```
+    pub fn try_get_buffered(&mut self, amount: usize) -> Option<Vec<Proxy>> {
+        let amount = amount.min(self.remaining());
+        if self.proxies.len() >= amount {
+            let proxies = self.proxies.split_off(self.proxies.len() - amount);
+            Some(self.hand_out(proxies))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator that yields proxies one at a time, fetching more whenever the internal
+    /// list runs dry. Errors from the API are yielded and end the iteration unless the iterator is
+    /// made [`resilient`][Iter::resilient]. Refills go through the same delays as
+    /// [`try_get`][Fetcher::try_get].
+    ///
+    /// Note that the iterator is effectively infinite. It only ends on an error (like hitting the
+    /// API's daily limit) or the [lifetime cap][Fetcher::with_lifetime_cap], so use something
+    /// like `.take(n)` to bound it.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// for proxy in fetcher.iter().take(20) {
+    ///     println!("{:?}", proxy);
+    /// }
+    /// ```
+    pub fn iter(&mut self) -> Iter<'_> {
+        Iter {
+            fetcher: self,
+            retry_delay: None,
+            done: false,
+        }
+    }
+
+    /// Consumes the `Fetcher` returning any proxies still left in the internal list.
+    pub fn drain(self) -> Vec<Proxy> {
+        let remaining = self.remaining();
+        let mut proxies = self.proxies;
+        proxies.truncate(remaining);
+        proxies
+    }
+}
+
+impl Default for Fetcher {
+    fn default() -> Self {
+        Self::new(Opts::default())
+    }
+}
+
+/// An endless iterator over a [`Fetcher`][Fetcher]'s proxies returned by
+/// [`Fetcher::iter`][Fetcher::iter].
+///
+/// The iterator ends after yielding an error from the API, or once the `Fetcher`'s
+/// [lifetime cap][Fetcher::with_lifetime_cap] is reached.
+#[derive(Debug)]
+pub struct Iter<'fetcher> {
+    fetcher: &'fetcher mut Fetcher,
+    retry_delay: Option<Duration>,
+    done: bool,
+}
+
+impl Iter<'_> {
+    /// Keeps going after errors that can clear up on their own, like `ApiError::RateLimit` or
+    /// `ApiError::Server`. These errors are still yielded, but the iterator waits for
+    /// `retry_delay` and then tries again instead of ending. Any other error, like
+    /// `ApiError::ApiKey`, still ends the iteration.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    /// use std::time::Duration;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// for proxy in fetcher.iter().resilient(Duration::from_secs(60)) {
+    ///     match proxy {
+    ///         Ok(proxy) => println!("Got {}", proxy.socket),
+    ///         Err(err) => eprintln!("Hit a snag: {}", err),
+    ///     }
+    /// }
+    /// ```
+    pub fn resilient(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = Some(retry_delay);
+        self
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Result<Proxy, ApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.fetcher.try_get(1) {
+            // Only comes up empty once the lifetime cap is hit
+            Ok(mut proxies) => {
+                let proxy = proxies.pop();
+                self.done = proxy.is_none();
+                proxy.map(Ok)
+            }
+            Err(err) => {
+                match self.retry_delay {
+                    Some(retry_delay) if err.is_retryable() => {
+                        debug!("Retrying in {:?} after: {}", retry_delay, err);
+                        thread::sleep(retry_delay)
+                    }
+                    _ => self.done = true,
+                }
+
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// A handle to a [`Fetcher`][Fetcher] that's prefetching proxies in the background. Constructed
+/// with [`Fetcher::spawn_prefetcher`][Fetcher::spawn_prefetcher].
+#[derive(Debug)]
+pub struct PrefetchHandle {
+    requests: Option<Sender<PrefetchRequest>>,
+    worker: Option<JoinHandle<Fetcher>>,
+    // Taken back from the worker once it stops
+    fetcher: Option<Fetcher>,
+}
+
+/// Internal
+#[derive(Debug)]
+struct PrefetchRequest {
+    amount: usize,
+    reply: Sender<Result<Vec<Proxy>, ApiError>>,
+}
+
+impl PrefetchHandle {
+    /// Works the same as [`Fetcher::try_get`][Fetcher::try_get] using the prefetched proxies first.
+    /// This also returns any error hit while prefetching.
+    pub fn try_get(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        if let Some(requests) = &self.requests {
+            let (reply, response) = mpsc::channel();
+            if requests.send(PrefetchRequest { amount, reply }).is_ok() {
+                if let Ok(result) = response.recv() {
+                    return result;
+                }
+            }
+        }
+
+        // The worker stopped, so carry on like a normal `Fetcher`
+        self.fetcher_mut().try_get(amount)
+    }
+
+    /// Stops prefetching and returns the `Fetcher` along with any proxies it has buffered.
+    pub fn shutdown(mut self) -> Fetcher {
+        self.fetcher_mut();
+        self.fetcher
+            .take()
+            .expect("The fetcher was just taken back")
+    }
+
+    fn fetcher_mut(&mut self) -> &mut Fetcher {
+        // Hanging up lets the worker know to stop
+        self.requests = None;
+        if let Some(worker) = self.worker.take() {
+            let fetcher = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            self.fetcher = Some(fetcher);
+        }
+
+        self.fetcher
+            .as_mut()
+            .expect("Either the worker or the handle has the fetcher")
+    }
+}
+
+/// Internal
+fn query_params(opts: &Opts) -> String {
+    opts.to_query_string().unwrap_or_else(|_| {
+        panic!(
+            "Failed to serialize url, please raise an issue to address this: {}",
+            constants::REPO_URI
+        )
+    })
+}
+
+/// Internal
+// Keeps the API key out of the logs
+fn redacted_query(opts: &Opts) -> String {
+    if opts.is_premium() {
+        query_params(&opts.with_api_key("<redacted>".to_string()))
+    } else {
+        query_params(opts)
+    }
+}
+
+/// Internal
+fn log_outcome<T>(result: &Result<Vec<T>, ApiError>) {
+    match result {
+        Ok(batch) => debug!("The API returned {} proxies", batch.len()),
+        Err(err) => debug!(
+            "Classified the API's response as `{}` (status: {:?}): {}",
+            err.kind(),
+            err.status(),
+            err
+        ),
+    }
+}
+
+/// Internal
+fn parse_response<T, E, P>(naive_resp: NaiveResponse, parse: P) -> Result<Vec<T>, ApiError>
+where
+    P: Fn(&str) -> Result<Vec<T>, E>,
+    E: ParseFailure,
+{
+    if naive_resp.ok() {
+        parse(&naive_resp.text).map_err(|err| match ApiError::from(naive_resp.text.clone()) {
+            // Not one of the known error messages, so keep what went wrong if it's informative
+            ApiError::UnrecognizedMessage { .. } | ApiError::Unknown { .. } => {
+                err.into_api_error(naive_resp)
+            }
+            known => known.with_response(naive_resp),
+        })
+    } else {
+        Err(ApiError::from(naive_resp))
+    }
+}
+
+/// Internal
+trait ParseFailure {
+    fn into_api_error(self, naive_resp: NaiveResponse) -> ApiError;
+}
+
+impl ParseFailure for serde_json::Error {
+    // Error messages from the API aren't JSON at all, so only JSON with the wrong shape is worth
+    // reporting as such
+    fn into_api_error(self, naive_resp: NaiveResponse) -> ApiError {
+        if self.is_data() {
+            ApiError::Parse {
+                source: self,
+                text: naive_resp.text,
+            }
+        } else {
+            ApiError::from(naive_resp)
+        }
+    }
+}
+
+impl ParseFailure for AddrParseError {
+    fn into_api_error(self, naive_resp: NaiveResponse) -> ApiError {
+        ApiError::from(naive_resp)
+    }
+}
+
+static SHARED: OnceLock<SharedFetcher> = OnceLock::new();
+
+/// A handle to a [`Fetcher`][Fetcher] that can be shared between threads.
+///
+/// Separate keyless `Fetcher`s already coordinate their delays, but each one keeps its own internal
+/// list, so extra proxies from one `Fetcher`'s requests can't be used by the others. Sharing a
+/// single `Fetcher` avoids this. Cloning a `SharedFetcher` gives another handle to the same
+/// `Fetcher`, and [`shared`][shared] returns a handle to a process-wide one.
+///
+/// ```no_run
+/// use lead_oxide::fetcher::{Fetcher, SharedFetcher};
+/// use std::thread;
+///
+/// let shared = SharedFetcher::new(Fetcher::default());
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let shared = shared.clone();
+///         thread::spawn(move || shared.try_get(2))
+///     })
+///     .collect();
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedFetcher {
+    fetcher: Arc<Mutex<Fetcher>>,
+}
+
+impl SharedFetcher {
+    /// Wraps `fetcher` so that it can be shared.
+    pub fn new(fetcher: Fetcher) -> Self {
+        Self {
+            fetcher: Arc::new(Mutex::new(fetcher)),
+        }
+    }
+
+    /// Same as [`Fetcher::try_get`][Fetcher::try_get] on the shared `Fetcher`.
+    pub fn try_get(&self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        self.lock().try_get(amount)
+    }
+
+    /// Locks the shared `Fetcher` for using any of its other methods. Other handles will block on
+    /// any method until the lock is released.
+    pub fn lock(&self) -> MutexGuard<'_, Fetcher> {
+        // A panic can't leave a `Fetcher` in an invalid state so a poisoned lock is still fine
+        self.fetcher
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Draws proxies evenly from several [`Fetcher`][Fetcher]s, like ones set up with different
+/// `Opts` profiles.
+///
+/// Proxies are taken one at a time from each member in turn. A member that runs out with
+/// `ApiError::NoProxy` or `ApiError::DailyLimit` is skipped for the rest of the call, and an error
+/// is only returned once every member has run out. Keyless members still share the usual delay
+/// between requests, so a pool doesn't make requests any faster than its members would on their
+/// own.
+///
+/// ```no_run
+/// use iso_country::Country;
+/// use lead_oxide::{
+///     fetcher::{Fetcher, FetcherPool},
+///     opts::Opts,
+///     types::Countries,
+/// };
+///
+/// let profile = |country| {
+///     Fetcher::new(
+///         Opts::builder()
+///             .countries(Countries::allow().country(country))
+///             .build(),
+///     )
+/// };
+/// let mut pool = FetcherPool::new(vec![profile(Country::US), profile(Country::CA)]);
+/// // Alternates between US and CA proxies
+/// let proxies = pool.try_get(4).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FetcherPool {
+    fetchers: Vec<Fetcher>,
+    current: usize,
+}
+
+impl FetcherPool {
+    pub fn new(fetchers: Vec<Fetcher>) -> Self {
+        Self {
+            fetchers,
+            current: 0,
+        }
+    }
+
+    /// Attempts to get `amount` proxies round-robin across the members, continuing from whichever
+    /// member is next after the last call. On an error the proxies taken so far are put back in
+    /// the members they came from, so nothing is lost.
+    pub fn try_get(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        // The member each proxy came from is kept around in case they need to be given back
+        let mut drawn = Vec::with_capacity(amount);
+        let mut exhausted = vec![false; self.fetchers.len()];
+        let mut last_err = ApiError::NoProxy { response: None };
+
+        while drawn.len() < amount {
+            if exhausted.iter().all(|&done| done) {
+                self.give_back(drawn);
+                return Err(last_err);
+            }
+
+            let i = self.current;
+            self.current = (self.current + 1) % self.fetchers.len();
+            if exhausted[i] {
+                continue;
+            }
+
+            match self.fetchers[i].try_get_one() {
+                Ok(proxy) => drawn.push((i, proxy)),
+                Err(err @ (ApiError::NoProxy { .. } | ApiError::DailyLimit { .. })) => {
+                    exhausted[i] = true;
+                    last_err = err;
+                }
+                Err(err) => {
+                    self.give_back(drawn);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(drawn.into_iter().map(|(_, proxy)| proxy).collect())
+    }
+
+    /// The `Fetcher`s in the pool.
+    pub fn fetchers(&self) -> &[Fetcher] {
+        &self.fetchers
+    }
+
+    /// Consumes the pool returning the `Fetcher`s.
+    pub fn into_inner(self) -> Vec<Fetcher> {
+        self.fetchers
+    }
+
+    fn give_back(&mut self, drawn: Vec<(usize, Proxy)>) {
+        for (i, proxy) in drawn {
+            self.fetchers[i].give_back(vec![proxy]);
+        }
+    }
+}
+
+/// Returns a handle to the process-wide [`SharedFetcher`][SharedFetcher]. It's created on first
+/// use from either `Fetcher::default()` or the `Fetcher` provided to
+/// [`configure_shared`][configure_shared]. This is also available as `lead_oxide::shared`.
+///
+/// ```no_run
+/// let proxies = lead_oxide::shared().try_get(3).unwrap();
+/// ```
+pub fn shared() -> SharedFetcher {
+    SHARED
+        .get_or_init(|| SharedFetcher::new(Fetcher::default()))
+        .clone()
+}
+
+/// Sets the `Fetcher` used by [`shared`][shared]. This can only be done once, before the shared
+/// `Fetcher` is first used, and the provided `fetcher` is handed back if it's too late.
+///
+/// ```no_run
+/// use lead_oxide::{fetcher::Fetcher, opts::Opts};
+///
+/// lead_oxide::configure_shared(Fetcher::new(Opts::any())).unwrap();
+/// let proxies = lead_oxide::shared().try_get(3).unwrap();
+/// ```
+pub fn configure_shared(fetcher: Fetcher) -> Result<(), Box<Fetcher>> {
+    let mut fetcher = Some(fetcher);
+    SHARED.get_or_init(|| SharedFetcher::new(fetcher.take().expect("Only initialized once")));
+
+    match fetcher {
+        Some(unused) => Err(Box::new(unused)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    // XXX: need to handle clearing the env var key once that's added in
+    const FREE_LIMIT: usize = 5;
+    const PREMIUM_LIMIT: usize = 20;
+
+    // Mocks the API's response to `opts` with `count` proxies
+    fn mock_response(opts: &Opts, count: usize) -> NaiveResponse {
+        const MOCK_PROXY: &str = r#"{
+            "ipPort": "1.2.3.4:4321",
+            "ip": "1.2.3.4",
+            "port": "4321",
+            "country": "CA",
+            "last_checked": "2020-01-01 01:01:01",
+            "proxy_level": "anonymous",
+            "type": "http",
+            "speed": "21",
+            "support": {
+                "https": null,
+                "get": null,
+                "post": null,
+                "cookies": null,
+                "referer": null,
+                "user_agent": null,
+                "google": null
+            }
+        }"#;
+
+        match opts.format {
+            Format::Json => {
+                // Like the API, the mocked proxies match the requested protocol
+                let mock_proxy = match opts.protocol {
+                    Some(protocol) => {
+                        let protocol = serde_json::to_string(&protocol).unwrap();
+                        MOCK_PROXY.replace(r#""http""#, &protocol)
+                    }
+                    None => MOCK_PROXY.to_string(),
+                };
+                let data = vec![mock_proxy; count].join(",");
+                NaiveResponse::new(
+                    200,
+                    format!(r#"{{"data": [{}], "count": {}}}"#, data, count),
+                )
+            }
+            Format::Txt => NaiveResponse::new(200, vec!["1.2.3.4:4321"; count].join("\n")),
+        }
+    }
+
+    // The parts of the `Opts` that a request was made with that the mocks care about
+    fn requested_opts(request: &ureq::Request) -> Opts {
+        let query = request.get_query().unwrap();
+        let params: HashMap<String, String> =
+            serde_urlencoded::from_str(query.trim_start_matches('?')).unwrap();
+
+        let mut builder = Opts::builder().format_txt(params["format"] == "txt");
+        if let Some(key) = params.get("api") {
+            builder = builder.api_key(key.to_owned());
+        }
+        if let Some(protocol) = params.get("type") {
+            builder = builder.protocol(protocol.parse().unwrap());
+        }
+
+        builder.build()
+    }
+
+    // Mocks the API by responding to whatever the request asked for
+    pub(super) struct MockTransport;
+
+    impl Transport for MockTransport {
+        fn call(&self, request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+            let opts = requested_opts(&request);
+            Ok(mock_response(&opts, opts.limit as usize))
+        }
+    }
+
+    // Like `MockTransport`, but always responds with this many proxies instead of the limit
+    struct Batches(usize);
+
+    impl Transport for Batches {
+        fn call(&self, request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+            Ok(mock_response(&requested_opts(&request), self.0))
+        }
+    }
+
+    // Like `MockTransport`, but the first few requests fail with a server error
+    struct FailFirst(AtomicUsize);
+
+    impl FailFirst {
+        fn new(failures: usize) -> Self {
+            Self(AtomicUsize::new(failures))
+        }
+    }
+
+    impl Transport for FailFirst {
+        fn call(&self, request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+            let failing = self
+                .0
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok();
+            if failing {
+                Ok(NaiveResponse::new(500, "Oops".to_string()))
+            } else {
+                MockTransport.call(request)
+            }
+        }
+    }
+
+    // Always responds with the same thing
+    struct Canned(u16, &'static str);
+
+    impl Transport for Canned {
+        fn call(&self, _request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+            let Self(status, text) = *self;
+            Ok(NaiveResponse::new(status, text.to_string()))
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        fn canned_error(status: u16, text: &'static str) -> ApiError {
+            let mut fetcher = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build())
+                .with_transport(Canned(status, text));
+            fetcher.try_get(1).unwrap_err()
+        }
+
+        #[test]
+        fn known_messages() {
+            assert!(matches!(
+                canned_error(200, "No proxy"),
+                ApiError::NoProxy { .. }
+            ));
+            assert!(matches!(
+                canned_error(
+                    200,
+                    "Invalid API. Get your API to make unlimited requests at \
+                     http://pubproxy.com/#premium"
+                ),
+                ApiError::ApiKey { .. }
+            ));
+            assert!(matches!(
+                canned_error(
+                    429,
+                    "You reached the maximum 50 requests for today. Get your API to make \
+                     unlimited requests at http://pubproxy.com/#premium"
+                ),
+                ApiError::DailyLimit { .. }
+            ));
+        }
+
+        #[test]
+        fn unknown_messages() {
+            assert!(matches!(
+                canned_error(200, "{ not json"),
+                ApiError::UnrecognizedMessage { .. }
+            ));
+            assert!(matches!(canned_error(200, ""), ApiError::Unknown { .. }));
+            assert!(matches!(
+                canned_error(404, "Not Found"),
+                ApiError::Client { status: 404, .. }
+            ));
+            assert!(matches!(
+                canned_error(503, "Service Unavailable"),
+                ApiError::Server { status: 503, .. }
+            ));
+        }
+
+        #[test]
+        fn parse() {
+            let text = r#"{"data": [{"ipPort": "not a socket"}], "count": 1}"#;
+            let err = canned_error(200, text);
+            assert!(std::error::Error::source(&err).is_some());
+            match err {
+                ApiError::Parse { source, text: body } => {
+                    assert!(source.is_data());
+                    assert_eq!(body, text);
+                }
+                err => panic!("Expected a parse error, got {:?}", err),
+            }
+        }
+
+        #[test]
+        fn transport() {
+            struct Unreachable;
+
+            impl Transport for Unreachable {
+                fn call(&self, _request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+                    Err(ApiError::Transport {
+                        message: "Connection refused".to_string(),
+                    })
+                }
+            }
+
+            let mut fetcher = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build())
+                .with_transport(Unreachable);
+            assert!(matches!(
+                fetcher.try_get(1),
+                Err(ApiError::Transport { .. })
+            ));
+            assert_eq!(fetcher.health().errors_by_kind["Transport"], 1);
+        }
+    }
+
+    mod functionality {
+        use iso_country::Country;
+
+        use super::*;
+        use crate::opts::OptsBuilder;
+        use crate::types::{Countries, Level, Protocol};
+
+        #[test]
+        #[serial]
+        fn api_key() {
+            let mut fetcher = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build());
+
+            let single = fetcher.try_get(1).unwrap();
+            let triple = fetcher.try_get(3).unwrap();
+            let the_rest = fetcher.drain();
+
+            assert_eq!(single.len(), 1);
+            assert_eq!(triple.len(), 3);
+            assert_eq!(PREMIUM_LIMIT, single.len() + triple.len() + the_rest.len());
+        }
+
+        #[test]
+        #[serial]
+        fn api_key_rotation() {
+            let mut fetcher = Fetcher::default().with_api_keys(vec![
+                "<key1>".to_string(),
+                "<key2>".to_string(),
+                "<key3>".to_string(),
+            ]);
+            assert!(fetcher.is_premium());
+
+            // Each request moves on to the next key, wrapping back around at the end
+            let proxies = fetcher.try_get(PREMIUM_LIMIT + 1).unwrap();
+            assert_eq!(proxies.len(), PREMIUM_LIMIT + 1);
+            assert_eq!(fetcher.current_key, 2);
+            let _ = fetcher.try_get(2 * PREMIUM_LIMIT).unwrap();
+            assert_eq!(fetcher.current_key, 1);
+
+            // No keys means no rotation
+            let fetcher = Fetcher::default().with_api_keys(Vec::new());
+            assert!(!fetcher.is_premium());
+        }
+
+        #[test]
+        #[serial]
+        fn keyless() {
+            let mut fetcher = Fetcher::default();
+
+            let single = fetcher.try_get(1).unwrap();
+            let triple = fetcher.try_get(3).unwrap();
+            let the_rest = fetcher.drain();
+
+            assert_eq!(single.len(), 1);
+            assert_eq!(triple.len(), 3);
+            assert_eq!(FREE_LIMIT, single.len() + triple.len() + the_rest.len());
+        }
+
+        #[test]
+        #[serial]
+        fn multiple_requests() {
+            // Multiple requests can be done with a single method call
+            for i in 0..=2 * FREE_LIMIT {
+                let mut fetcher = Fetcher::default();
+                let proxies = fetcher.try_get(i).unwrap();
+                assert_eq!(proxies.len(), i);
+            }
+        }
+
+        #[test]
+        #[serial]
+        fn multiple_fetchers() {
+            // Each fetcher should be independent
+            let mut default = Fetcher::default();
+            let mut premium = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build());
+            let mut custom = Fetcher::new(
+                Opts::builder()
+                    .level(Level::Elite)
+                    .cookies(true)
+                    .countries(Countries::allow().country(Country::CA))
+                    .build(),
+            );
+
+            let single = default.try_get(1).unwrap();
+            let double = premium.try_get(2).unwrap();
+            let triple = custom.try_get(3).unwrap();
+            assert_eq!(single.len(), 1);
+            assert_eq!(double.len(), 2);
+            assert_eq!(triple.len(), 3);
+            assert_eq!(default.drain().len(), FREE_LIMIT - single.len());
+            assert_eq!(premium.drain().len(), PREMIUM_LIMIT - double.len());
+            assert_eq!(custom.drain().len(), FREE_LIMIT - triple.len());
+        }
+
+        #[test]
+        #[serial]
+        fn adaptive() {
+            let opts = Opts::builder().level(Level::Elite).post(true).build();
+            let mut fetcher = Fetcher::new(opts.clone());
+
+            // Nothing gets relaxed when the API returns proxies
+            let (proxies, relaxed) = fetcher
+                .try_get_adaptive(2, &[Filter::Post, Filter::Level])
+                .unwrap();
+            assert_eq!(proxies.len(), 2);
+            assert!(relaxed.is_empty());
+            assert_eq!(fetcher.opts, opts);
+        }
+
+        #[test]
+        fn adaptive_relaxing() {
+            // Nothing matches as long as `post` is requested
+            struct NoPost;
+
+            impl Transport for NoPost {
+                fn call(&self, request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+                    if request.get_query().unwrap().contains("post=") {
+                        Ok(NaiveResponse::new(200, "No proxy".to_string()))
+                    } else {
+                        MockTransport.call(request)
+                    }
+                }
+            }
+
+            let opts = Opts::builder()
+                .api_key("<key>".to_string())
+                .level(Level::Elite)
+                .post(true)
+                .build();
+            let mut fetcher = Fetcher::new(opts.clone()).with_transport(NoPost);
+
+            // Unset filters are skipped and relaxing stops once the request succeeds
+            let (proxies, relaxed) = fetcher
+                .try_get_adaptive(2, &[Filter::Https, Filter::Post, Filter::Level])
+                .unwrap();
+            assert_eq!(proxies.len(), 2);
+            assert_eq!(relaxed, vec![Filter::Post]);
+            assert_eq!(fetcher.health().requests_made, 2);
+            assert_eq!(fetcher.opts, opts);
+
+            // and the error is returned once there's nothing left to relax
+            let mut fetcher = Fetcher::new(opts).with_transport(NoPost);
+            let result = fetcher.try_get_adaptive(2, &[Filter::Level]);
+            assert!(matches!(result, Err(ApiError::NoProxy { .. })));
+        }
+
+        #[test]
+        #[serial]
+        fn raw_proxies() {
+            let mut fetcher = Fetcher::default();
+
+            let raw_proxies = fetcher.try_get_raw_proxies(FREE_LIMIT + 1).unwrap();
+            assert_eq!(raw_proxies.len(), FREE_LIMIT + 1);
+            assert_eq!(raw_proxies[0].ip_port, "1.2.3.4:4321");
+            assert_eq!(raw_proxies[0].last_checked, "2020-01-01 01:01:01");
+
+            // Raw proxies are kept separate from the regular ones
+            assert_eq!(fetcher.raw_proxies.len(), FREE_LIMIT - 1);
+            assert!(fetcher.proxies.is_empty());
+        }
+
+        #[test]
+        #[serial]
+        fn until_satisfied() {
+            let mut fetcher = Fetcher::default();
+
+            // Keeps going until the condition is met and returns everything
+            let proxies = fetcher
+                .try_get_until_satisfied(|proxies| proxies.len() > FREE_LIMIT)
+                .unwrap();
+            assert_eq!(proxies.len(), 2 * FREE_LIMIT);
+            assert!(fetcher.proxies.is_empty());
+
+            // A condition that's already satisfied doesn't fetch anything
+            let proxies = fetcher.try_get_until_satisfied(|_| true).unwrap();
+            assert!(proxies.is_empty());
+            assert_eq!(fetcher.health().requests_made, 2);
+        }
+
+        #[test]
+        #[serial]
+        fn freshest() {
+            let mut fetcher = Fetcher::default();
+            let _ = fetcher.try_get(1).unwrap();
+
+            // Age the buffered proxies by differing amounts
+            let now = chrono::Utc::now().naive_utc();
+            for (i, proxy) in fetcher.proxies.iter_mut().enumerate() {
+                proxy.socket.set_port(i as u16);
+                proxy.last_checked = now - chrono::Duration::minutes([3, 1, 4, 2][i]);
+            }
+
+            let freshest = fetcher.try_get_freshest(2).unwrap();
+            let ports: Vec<_> = freshest.iter().map(|proxy| proxy.socket.port()).collect();
+            assert_eq!(ports, [1, 3]);
+            assert_eq!(fetcher.proxies.len(), FREE_LIMIT - 3);
+        }
+
+        #[test]
+        #[serial]
+        fn sorted() {
+            let mut fetcher = Fetcher::default();
+            let _ = fetcher.try_get(1).unwrap();
+
+            // Mix up the buffered proxies
+            let now = chrono::Utc::now().naive_utc();
+            for (i, proxy) in fetcher.proxies.iter_mut().enumerate() {
+                proxy.socket.set_port(i as u16);
+                proxy.time_to_connect = Duration::from_secs([3, 1, 4, 2][i]);
+                proxy.last_checked = now - chrono::Duration::minutes([2, 1, 4, 3][i]);
+            }
+            let mut other = Fetcher {
+                proxies: fetcher.proxies.clone(),
+                ..Fetcher::default()
+            };
+
+            let ports = |proxies: Vec<Proxy>| -> Vec<_> {
+                proxies.iter().map(|proxy| proxy.socket.port()).collect()
+            };
+            let by_connect = fetcher.try_get_sorted(3, SortKey::TimeToConnect).unwrap();
+            assert_eq!(ports(by_connect), [1, 3, 2]);
+            let by_checked = other.try_get_sorted(3, SortKey::LastChecked).unwrap();
+            assert_eq!(ports(by_checked), [2, 3, 1]);
+
+            // The internal list is left as is
+            assert_eq!(ports(fetcher.drain()), [0]);
+        }
+
+        #[test]
+        fn prefer_ipv4() {
+            use crate::proxy::test_util::proxy;
+
+            let socket = |socket: &str| proxy().with_socket(socket);
+            let buffered = vec![
+                socket("1.1.1.1:80"),
+                socket("[2001:db8::1]:80"),
+                socket("2.2.2.2:80"),
+                socket("[2001:db8::2]:80"),
+            ];
+            let sockets = |proxies: Vec<Proxy>| -> Vec<String> {
+                proxies
+                    .iter()
+                    .map(|proxy| proxy.socket.to_string())
+                    .collect()
+            };
+
+            // IPv4 proxies come first with IPv6 only filling in the rest
+            let mut fetcher = Fetcher {
+                proxies: buffered.clone(),
+                ..Fetcher::default().prefer_ipv4(true)
+            };
+            assert_eq!(
+                sockets(fetcher.try_get(3).unwrap()),
+                ["1.1.1.1:80", "2.2.2.2:80", "[2001:db8::2]:80"]
+            );
+            assert_eq!(sockets(fetcher.drain()), ["[2001:db8::1]:80"]);
+
+            // Otherwise they're handed out like normal
+            let mut fetcher = Fetcher {
+                proxies: buffered,
+                ..Fetcher::default()
+            };
+            assert_eq!(
+                sockets(fetcher.try_get(2).unwrap()),
+                ["2.2.2.2:80", "[2001:db8::2]:80"]
+            );
+        }
+
+        #[cfg(feature = "shuffle")]
+        #[test]
+        #[serial]
+        fn shuffled() {
+            let mut fetcher = Fetcher::default();
+            let _ = fetcher.try_get(1).unwrap();
+            for (i, proxy) in fetcher.proxies.iter_mut().enumerate() {
+                proxy.socket.set_port(i as u16);
+            }
+            let buffered = fetcher.proxies.clone();
+            let ports = |proxies: &[Proxy]| -> Vec<_> {
+                proxies.iter().map(|proxy| proxy.socket.port()).collect()
+            };
+
+            // The same seed always gives the same order
+            let orders: Vec<_> = (0..2)
+                .map(|_| {
+                    let mut seeded = Fetcher {
+                        proxies: buffered.clone(),
+                        ..Fetcher::default()
+                    }
+                    .with_shuffle_seed(1337);
+                    ports(&seeded.try_get_shuffled(buffered.len()).unwrap())
+                })
+                .collect();
+            assert_eq!(orders[0], orders[1]);
+            assert_ne!(orders[0], ports(&buffered));
+
+            // and the shuffled proxies are still the same proxies
+            let mut sorted = orders[0].clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, ports(&buffered));
+
+            // Different seeds should give a different order for at least one of them
+            let other_orders: Vec<_> = (0..8)
+                .map(|seed| {
+                    let mut seeded = Fetcher {
+                        proxies: buffered.clone(),
+                        ..Fetcher::default()
+                    }
+                    .with_shuffle_seed(seed);
+                    ports(&seeded.try_get_shuffled(buffered.len()).unwrap())
+                })
+                .collect();
+            assert!(other_orders.iter().any(|order| *order != orders[0]));
+
+            // Unseeded fetchers still shuffle
+            let proxies = fetcher.try_get_shuffled(FREE_LIMIT - 1).unwrap();
+            assert_eq!(proxies.len(), FREE_LIMIT - 1);
+        }
+
+        #[test]
+        fn pool() {
+            use crate::{opts::OptsBuilder, types::Protocol};
+
+            let premium =
+                |builder: OptsBuilder| Fetcher::new(builder.api_key("<key>".to_string()).build());
+            let by_protocol = |protocol| premium(Opts::builder().protocol(protocol));
+            let protocols = |proxies: &[Proxy]| -> Vec<_> {
+                proxies.iter().map(|proxy| proxy.protocol).collect()
+            };
+
+            // Draws evenly from every member, picking up where the last call left off
+            let mut pool = FetcherPool::new(vec![
+                by_protocol(Protocol::Http),
+                by_protocol(Protocol::Socks4),
+                by_protocol(Protocol::Socks5),
+            ]);
+            let proxies = pool.try_get(4).unwrap();
+            assert_eq!(
+                protocols(&proxies),
+                [
+                    Protocol::Http,
+                    Protocol::Socks4,
+                    Protocol::Socks5,
+                    Protocol::Http
+                ]
+            );
+            let proxies = pool.try_get(2).unwrap();
+            assert_eq!(protocols(&proxies), [Protocol::Socks4, Protocol::Socks5]);
+
+            // Exhausted members are skipped
+            let mut pool = FetcherPool::new(vec![
+                premium(Opts::builder()).with_transport(Canned(200, "No proxy")),
+                by_protocol(Protocol::Socks4),
+            ]);
+            let proxies = pool.try_get(3).unwrap();
+            assert_eq!(protocols(&proxies), [Protocol::Socks4; 3]);
+
+            // Only failing once every member is exhausted
+            let mut pool = FetcherPool::new(vec![
+                premium(Opts::builder()).with_transport(Canned(200, "No proxy")),
+                by_protocol(Protocol::Socks4).with_lifetime_cap(1),
+            ]);
+            assert!(matches!(pool.try_get(2), Err(ApiError::NoProxy { .. })));
+            // and the proxy that was drawn goes back to its member
+            let fetchers = pool.into_inner();
+            assert_eq!(fetchers[1].buffered(), PREMIUM_LIMIT);
+            assert!(FetcherPool::new(Vec::new()).try_get(1).is_err());
+            assert!(FetcherPool::new(Vec::new()).try_get(0).unwrap().is_empty());
+
+            // Other errors are returned right away
+            let mut pool = FetcherPool::new(vec![
+                by_protocol(Protocol::Http),
+                premium(Opts::builder()).with_transport(Canned(500, "Oops")),
+            ]);
+            assert!(matches!(
+                pool.try_get(2),
+                Err(ApiError::Server { status: 500, .. })
+            ));
+            assert_eq!(pool.fetchers()[0].buffered(), PREMIUM_LIMIT);
+        }
+
+        #[test]
+        #[serial]
+        fn one() {
+            let mut fetcher = Fetcher::default().with_lifetime_cap(2);
+
+            // Uses the same internal list as `try_get`
+            let _ = fetcher.try_get_one().unwrap();
+            assert_eq!(fetcher.buffered(), FREE_LIMIT - 1);
+            let _ = fetcher.try_get_one().unwrap();
+            assert_eq!(fetcher.buffered(), FREE_LIMIT - 2);
+
+            assert!(matches!(
+                fetcher.try_get_one(),
+                Err(ApiError::NoProxy { .. })
+            ));
+        }
+
+        #[test]
+        #[serial]
+        fn lifetime_cap() {
+            let mut fetcher = Fetcher::default().with_lifetime_cap(FREE_LIMIT + 2);
+
+            assert_eq!(fetcher.try_get(FREE_LIMIT).unwrap().len(), FREE_LIMIT);
+            assert_eq!(fetcher.try_get_freshest(1).unwrap().len(), 1);
+            // Only part of the internal list fits under the cap
+            assert_eq!(fetcher.try_get_until_satisfied(|_| true).unwrap().len(), 1);
+            assert_eq!(fetcher.proxies.len(), FREE_LIMIT - 2);
+
+            // And after that nothing more is requested or returned
+            let requests_made = fetcher.health().requests_made;
+            assert!(fetcher.try_get(1).unwrap().is_empty());
+            assert!(fetcher.try_get_raw_proxies(1).unwrap().is_empty());
+            assert_eq!(fetcher.health().requests_made, requests_made);
+            assert!(fetcher.drain().is_empty());
+        }
+
+        #[test]
+        #[serial]
+        fn channel_hand_off() {
+            use std::sync::mpsc;
+
+            let mut fetcher = Fetcher::default();
+            let _ = fetcher.try_get(1).unwrap();
+
+            let (sender, receiver) = mpsc::sync_channel(FREE_LIMIT);
+            assert_eq!(fetcher.drain_into(&sender), FREE_LIMIT - 1);
+            assert!(fetcher.proxies.is_empty());
+            assert_eq!(receiver.try_iter().count(), FREE_LIMIT - 1);
+
+            // Proxies are kept when nobody is listening
+            let _ = fetcher.try_get(1).unwrap();
+            drop(receiver);
+            assert_eq!(fetcher.drain_into(&sender), 0);
+            assert_eq!(fetcher.proxies.len(), FREE_LIMIT - 1);
+
+            // The feeder keeps fetching until it hits the cap
+            let (sender, receiver) = mpsc::sync_channel(1);
+            let feeder = Fetcher::default()
+                .with_lifetime_cap(FREE_LIMIT + 2)
+                .spawn_feeder(sender);
+            assert_eq!(receiver.iter().count(), FREE_LIMIT + 2);
+            feeder.join().unwrap().unwrap();
+        }
+
+        #[test]
+        #[serial]
+        fn larger_batches() {
+            let fetcher = Fetcher::default();
+            assert_eq!(fetcher.health().batch_size, FREE_LIMIT);
+
+            // The API returning more than expected bumps the expectation
+            let mut fetcher = fetcher.with_transport(Batches(FREE_LIMIT + 3));
+            let proxies = fetcher.try_get(FREE_LIMIT + 1);
+
+            assert_eq!(proxies.unwrap().len(), FREE_LIMIT + 1);
+            let report = fetcher.health();
+            assert_eq!(report.batch_size, FREE_LIMIT + 3);
+            // and a single request was enough to fulfill it
+            assert_eq!(report.requests_made, 1);
+            assert_eq!(report.buffered, 2);
+        }
+
+        #[test]
+        #[serial]
+        fn smaller_batches() {
+            let mut fetcher = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build())
+                .with_transport(Batches(PREMIUM_LIMIT / 2));
+
+            // Short batches just take more requests without lowering the expectation
+            let proxies = fetcher.try_get(PREMIUM_LIMIT + 1);
+
+            assert_eq!(proxies.unwrap().len(), PREMIUM_LIMIT + 1);
+            let report = fetcher.health();
+            assert_eq!(report.batch_size, PREMIUM_LIMIT);
+            assert_eq!(report.requests_made, 3);
+            assert_eq!(report.buffered, PREMIUM_LIMIT / 2 - 1);
+        }
+
+        #[test]
+        #[serial]
+        fn shared_fetcher() {
+            // The only test touching the process-wide fetcher, so it's not initialized yet
+            assert!(configure_shared(Fetcher::default().with_lifetime_cap(3)).is_ok());
+            assert!(configure_shared(Fetcher::default()).is_err());
+
+            // Every handle uses the same fetcher
+            assert_eq!(shared().try_get(2).unwrap().len(), 2);
+            assert_eq!(shared().lock().health().buffered, FREE_LIMIT - 2);
+            assert_eq!(shared().try_get(2).unwrap().len(), 1);
+
+            let handle = SharedFetcher::new(Fetcher::default());
+            let other = handle.clone();
+            let _ = thread::spawn(move || other.try_get(1).unwrap())
+                .join()
+                .unwrap();
+            assert_eq!(handle.lock().health().buffered, FREE_LIMIT - 1);
+        }
+
+        #[test]
+        #[serial]
+        fn asn_filtering() {
+            use std::net::{IpAddr, Ipv4Addr};
+
+            let lookup = |ip: IpAddr| {
+                if ip == IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)) {
+                    Some(15169)
+                } else {
+                    None
+                }
+            };
+
+            // Other ASNs are kept
+            let mut fetcher = Fetcher::default()
+                .with_asn_lookup(lookup)
+                .exclude_asns(&[16509]);
+            fetcher.fetch_and_store().unwrap();
+            assert_eq!(fetcher.proxies.len(), FREE_LIMIT);
+
+            // While matching ones get dropped
+            let mut fetcher = Fetcher::default()
+                .with_asn_lookup(lookup)
+                .exclude_asns(&[16509, 15169]);
+            fetcher.fetch_and_store().unwrap();
+            assert!(fetcher.proxies.is_empty());
+
+            // and excluding does nothing without a lookup
+            let mut fetcher = Fetcher::default().exclude_asns(&[15169]);
+            fetcher.fetch_and_store().unwrap();
+            assert_eq!(fetcher.proxies.len(), FREE_LIMIT);
+        }
+
+        #[test]
+        #[serial]
+        fn max_connect_client_side() {
+            // The mocked proxies take 21 seconds to connect
+            let opts = Opts::builder()
+                .max_connect_client_side(Duration::from_secs_f64(20.5))
+                .build();
+            let mut fetcher = Fetcher::new(opts);
+            fetcher.fetch_and_store().unwrap();
+            assert!(fetcher.proxies.is_empty());
+
+            let opts = Opts::builder()
+                .max_connect_client_side(Duration::from_secs(21))
+                .build();
+            let mut fetcher = Fetcher::new(opts);
+            fetcher.fetch_and_store().unwrap();
+            assert_eq!(fetcher.proxies.len(), FREE_LIMIT);
+        }
+
+        #[test]
+        fn excluded_allowlist() {
+            // Merging can still end up excluding everything the allowlist asks for
+            let allow_ca = Opts::builder()
+                .api_key("<key>".to_string())
+                .countries(Countries::allow().country(Country::CA))
+                .build();
+            let opts = allow_ca.merge(&Opts::builder().exclude_countries(&[Country::CA]).build());
+            let mut fetcher = Fetcher::new(opts);
+
+            // So every mocked proxy gets dropped until the fetcher gives up
+            assert!(matches!(
+                fetcher.try_get(1),
+                Err(ApiError::NoProxy { response: None })
+            ));
+            assert_eq!(fetcher.health().requests_made, constants::MAX_EMPTY_FETCHES);
+            assert_eq!(fetcher.buffered(), 0);
+        }
+
+        #[test]
+        fn port_range() {
+            use std::sync::atomic::AtomicU8;
+
+            // Only one proxy from each response is within the range
+            struct MixedPorts(Arc<AtomicU8>);
+
+            impl Transport for MixedPorts {
+                fn call(&self, _request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+                    let n = self.0.fetch_add(1, Ordering::SeqCst);
+                    let text = [80, 443, 8080, 3128, 1080]
+                        .iter()
+                        .map(|port| format!("10.0.0.{}:{}", n, port))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    Ok(NaiveResponse::new(200, text))
+                }
+            }
+
+            let opts = Opts::builder()
+                .api_key("<key>".to_string())
+                .format_txt(true)
+                .port_range(8000..=9000)
+                .build();
+            let requests = Arc::new(AtomicU8::new(0));
+            let mut fetcher = Fetcher::new(opts).with_transport(MixedPorts(Arc::clone(&requests)));
+
+            // Keeps requesting until enough proxies are within the range
+            let proxies = fetcher.try_get(3).unwrap();
+            assert_eq!(proxies.len(), 3);
+            assert!(proxies.iter().all(|proxy| proxy.socket.port() == 8080));
+            assert_eq!(requests.load(Ordering::SeqCst), 3);
+        }
+
+        #[test]
+        #[serial]
+        fn iteration() {
+            let mut fetcher = Fetcher::default().with_lifetime_cap(FREE_LIMIT + 1);
+            let proxies: Result<Vec<_>, _> = fetcher.iter().collect();
+            assert_eq!(proxies.unwrap().len(), FREE_LIMIT + 1);
+
+            // Errors end the iteration by default
+            let mut fetcher = Fetcher::default().with_transport(FailFirst::new(2));
+            let results: Vec<_> = fetcher.iter().collect();
+            assert_eq!(results.len(), 1);
+            assert!(matches!(
+                results[0],
+                Err(ApiError::Server { status: 500, .. })
+            ));
+
+            // But a resilient iterator keeps on going
+            let results: Vec<_> = fetcher
+                .iter()
+                .resilient(Duration::from_millis(1))
+                .take(3)
+                .collect();
+            assert!(results[0].is_err());
+            assert!(results[1].is_ok());
+            assert!(results[2].is_ok());
+        }
+
+        #[test]
+        fn partial_failure() {
+            use std::sync::atomic::AtomicU8;
+
+            // Only the second request fails
+            struct FailSecond(AtomicU8);
+
+            impl Transport for FailSecond {
+                fn call(&self, request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+                    if self.0.fetch_add(1, Ordering::SeqCst) == 1 {
+                        Ok(NaiveResponse::new(500, "Oops".to_string()))
+                    } else {
+                        MockTransport.call(request)
+                    }
+                }
+            }
+
+            let mut fetcher = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build())
+                .with_transport(FailSecond(AtomicU8::new(0)));
+
+            // The proxies from the first request are kept after the second fails
+            let amount = PREMIUM_LIMIT + PREMIUM_LIMIT / 2;
+            assert!(fetcher.try_get(amount).is_err());
+            assert_eq!(fetcher.buffered(), PREMIUM_LIMIT);
+
+            // So retrying only needs one more request
+            assert_eq!(fetcher.try_get(amount).unwrap().len(), amount);
+            assert_eq!(fetcher.buffered(), PREMIUM_LIMIT / 2);
+            assert_eq!(fetcher.health().requests_made, 3);
+        }
+
+        #[test]
+        #[serial]
+        fn retry() {
+            let policy = RetryPolicy {
+                max_attempts: 3,
+                base_backoff: Duration::from_millis(1),
+            };
+
+            // Recovers as long as there's an attempt left
+            let mut fetcher = Fetcher::default().with_transport(FailFirst::new(2));
+            assert_eq!(fetcher.try_get_with_retry(1, policy).unwrap().len(), 1);
+            assert_eq!(fetcher.health().requests_made, 3);
+
+            // and otherwise returns the last error
+            let mut fetcher = Fetcher::default().with_transport(FailFirst::new(3));
+            let result = fetcher.try_get_with_retry(1, policy);
+            assert!(matches!(result, Err(ApiError::Server { status: 500, .. })));
+            assert_eq!(fetcher.health().requests_made, 3);
+        }
+
+        #[test]
+        #[serial]
+        fn peek() {
+            let mut fetcher = Fetcher::default();
+            assert!(fetcher.peek().is_empty());
+            assert_eq!(fetcher.buffered(), 0);
+
+            let _ = fetcher.try_get(1).unwrap();
+            assert_eq!(fetcher.buffered(), FREE_LIMIT - 1);
+            let peeked = fetcher.peek().to_vec();
+
+            // Taking up to what's buffered doesn't make a request
+            assert_eq!(fetcher.try_get(FREE_LIMIT - 1).unwrap(), peeked);
+            assert_eq!(fetcher.health().requests_made, 1);
+        }
+
+        #[test]
+        #[serial]
+        fn clear() {
+            let mut fetcher = Fetcher::default();
+            assert_eq!(fetcher.clear(), 0);
+
+            let _ = fetcher.try_get(1).unwrap();
+            assert_eq!(fetcher.clear(), FREE_LIMIT - 1);
+            assert_eq!(fetcher.buffered(), 0);
 
-                while self.proxies.len() < amount {
-                    // Delay to prevent rate limiting
-                    let delta = Instant::now().duration_since(*last_fetched);
-                    if delta < constants::DELAY {
-                        thread::sleep(constants::DELAY - delta);
-                    }
+            // The fetcher can still be used afterwards
+            assert_eq!(fetcher.try_get(1).unwrap().len(), 1);
+            assert_eq!(fetcher.health().requests_made, 2);
+        }
 
-                    let mut proxies = self.fetch(&mut request)?;
-                    self.proxies.append(&mut proxies);
+        #[test]
+        #[serial]
+        fn take_buffered() {
+            let mut fetcher = Fetcher::default();
+            assert!(fetcher.take_buffered().is_empty());
 
-                    // Update the request time
-                    *last_fetched = Instant::now();
-                }
-            }
+            let _ = fetcher.try_get(1).unwrap();
+            assert_eq!(fetcher.take_buffered().len(), FREE_LIMIT - 1);
+            assert_eq!(fetcher.buffered(), 0);
+            assert_eq!(fetcher.health().requests_made, 1);
 
-            Ok(self.proxies.split_off(self.proxies.len() - amount))
+            // The fetcher can still be used afterwards
+            assert_eq!(fetcher.try_get(2).unwrap().len(), 2);
+            assert_eq!(fetcher.health().requests_made, 2);
+            assert_eq!(fetcher.drain().len(), FREE_LIMIT - 2);
         }
-    }
 
-    fn request_builder(&self) -> ureq::Request {
-        let params = serde_urlencoded::to_string(&self.opts).unwrap_or_else(|_| {
-            panic!(
-                "Failed to serialize url, please raise an issue to address this: {}",
-                constants::REPO_URI
-            )
-        });
-        ureq::get(constants::API_URI).query_str(&params).build()
-    }
+        #[test]
+        fn redacted_query() {
+            let keyless = Opts::builder().post(true).build();
+            assert_eq!(super::redacted_query(&keyless), query_params(&keyless));
 
-    fn fetch(&self, request: &mut ureq::Request) -> Result<Vec<Proxy>, ApiError> {
-        if cfg!(not(test)) {
-            let resp = request.call();
-            let naive_resp = NaiveResponse::from(resp);
+            let premium = Opts::builder().api_key("secret".to_string()).build();
+            let redacted = super::redacted_query(&premium);
+            assert!(!redacted.contains("secret"), "{}", redacted);
+            assert!(redacted.contains("api=%3Credacted%3E"), "{}", redacted);
+        }
 
-            if naive_resp.ok() {
-                proxies_from_json(&naive_resp.text).map_err(|_| ApiError::from(naive_resp))
-            } else {
-                Err(ApiError::from(naive_resp))
-            }
-        } else {
-            use chrono::naive::NaiveDate;
-            use iso_country::Country;
+        #[test]
+        fn user_agent() {
+            let request = Fetcher::request_builder(&Opts::default());
+            assert_eq!(
+                request.header("User-Agent"),
+                Some(concat!("lead-oxide/", env!("CARGO_PKG_VERSION")))
+            );
 
-            use crate::{
-                proxy::Supports,
-                types::{Level, Protocol},
-            };
+            let opts = Opts::builder()
+                .user_agent("my-scraper/1.0".to_string())
+                .build();
+            let request = Fetcher::request_builder(&opts);
+            assert_eq!(request.header("User-Agent"), Some("my-scraper/1.0"));
+        }
 
+        #[test]
+        #[serial]
+        fn api_base() {
             use std::{
-                iter,
-                net::{Ipv4Addr, SocketAddrV4},
-                time::Duration,
+                io::{Read, Write},
+                net::TcpListener,
             };
 
-            // TODO: is there a better way to mock the api response? It would be nice to test that
-            // errors get interpreted right too. And if we could panic then we can test that the
-            // mutex getting poisoned works right
-            // TODO: Yes, just use something like wiremock and allow for setting the API url
-            Ok(iter::repeat(Proxy {
-                socket: SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 4321),
-                country: Country::CA,
-                last_checked: NaiveDate::from_ymd_opt(2020, 1, 1)
-                    .unwrap()
-                    .and_hms_opt(1, 1, 1)
-                    .unwrap(),
-                level: Level::Anonymous,
-                protocol: Protocol::Http,
-                time_to_connect: Duration::from_secs(21),
-                supports: Supports::default(),
-            })
-            .take(self.opts.limit as usize)
-            .collect())
+            // A tiny mock server that responds to a single request
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let api_base = format!("http://{}/api/proxy", listener.local_addr().unwrap());
+            let server = thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 1024];
+                let len = stream.read(&mut request).unwrap();
+
+                let body = mock_response(&Opts::default(), FREE_LIMIT).text;
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                String::from_utf8_lossy(&request[..len]).into_owned()
+            });
+
+            let mut fetcher = Fetcher::new(Opts::builder().api_base(api_base).build())
+                .with_transport(UreqTransport);
+            assert_eq!(fetcher.try_get(FREE_LIMIT).unwrap().len(), FREE_LIMIT);
+
+            let request = server.join().unwrap();
+            assert!(request.starts_with("GET /api/proxy?"), "{}", request);
+            assert!(request.contains("format=json"));
         }
-    }
 
-    /// Consumes the `Fetcher` returning any proxies still left in the internal list.
-    pub fn drain(self) -> Vec<Proxy> {
-        self.proxies
-    }
-}
+        #[test]
+        fn transport_timeout() {
+            use std::net::TcpListener;
 
-impl Default for Fetcher {
-    fn default() -> Self {
-        Self::new(Opts::default())
-    }
-}
+            // Accepts connections, but never responds
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
 
-#[cfg(test)]
-mod tests {
-    use serial_test::serial;
+            let mut request = ureq::get(&url);
+            set_timeouts(&mut request, Duration::from_millis(10));
+            let start = Instant::now();
+            let result = UreqTransport.call(request.build());
+            assert!(start.elapsed() < Duration::from_secs(1));
+            match result {
+                Err(err @ ApiError::Transport { .. }) => assert!(err.is_retryable()),
+                _ => panic!("Expected a transport error"),
+            }
+        }
 
-    use super::*;
+        #[test]
+        #[serial]
+        fn try_get_buffered() {
+            let mut fetcher = Fetcher::default();
+            assert_eq!(fetcher.try_get_buffered(1), None);
+            assert_eq!(fetcher.health().requests_made, 0);
 
-    // XXX: need to handle clearing the env var key once that's added in
-    const FREE_LIMIT: usize = 5;
-    const PREMIUM_LIMIT: usize = 20;
+            let _ = fetcher.try_get(1).unwrap();
+            let peeked = fetcher.peek().to_vec();
+            // Asking for more than is buffered leaves everything alone
+            assert_eq!(fetcher.try_get_buffered(FREE_LIMIT), None);
+            assert_eq!(fetcher.try_get_buffered(FREE_LIMIT - 1), Some(peeked));
+            assert_eq!(fetcher.try_get_buffered(0), Some(Vec::new()));
+            assert_eq!(fetcher.health().requests_made, 1);
+        }
 
-    mod functionality {
-        use iso_country::Country;
+        #[test]
+        #[serial]
+        fn dedupe() {
+            // Every mocked proxy is the same so only the first is kept
+            let mut fetcher = Fetcher::new(Opts::builder().dedupe(true).build());
+            assert_eq!(fetcher.try_get(1).unwrap().len(), 1);
+            assert!(fetcher.proxies.is_empty());
 
-        use super::*;
-        use crate::types::{Countries, Level};
+            // Including across requests
+            fetcher.fetch_and_store().unwrap();
+            assert!(fetcher.proxies.is_empty());
+            assert_eq!(fetcher.health().requests_made, 2);
+
+            // Asking for more than that gives up instead of requesting duplicates forever
+            let opts = Opts::builder()
+                .api_key("<key>".to_string())
+                .dedupe(true)
+                .build();
+            let mut fetcher = Fetcher::new(opts);
+            assert!(matches!(
+                fetcher.try_get(2),
+                Err(ApiError::NoProxy { response: None })
+            ));
+            assert_eq!(
+                fetcher.health().requests_made,
+                1 + constants::MAX_EMPTY_FETCHES
+            );
+            assert_eq!(fetcher.buffered(), 1);
+
+            // While duplicates are kept by default
+            let mut fetcher = Fetcher::default();
+            fetcher.fetch_and_store().unwrap();
+            assert_eq!(fetcher.proxies.len(), FREE_LIMIT);
+        }
 
         #[test]
         #[serial]
-        fn api_key() {
-            let mut fetcher = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build());
+        fn txt_format() {
+            use crate::types::Protocol;
+            use iso_country::Country;
 
-            let single = fetcher.try_get(1).unwrap();
-            let triple = fetcher.try_get(3).unwrap();
-            let the_rest = fetcher.drain();
+            let opts = Opts::builder()
+                .format_txt(true)
+                .protocol(Protocol::Socks5)
+                .https(true)
+                .build();
+            let mut fetcher = Fetcher::new(opts);
 
-            assert_eq!(single.len(), 1);
-            assert_eq!(triple.len(), 3);
-            assert_eq!(PREMIUM_LIMIT, single.len() + triple.len() + the_rest.len());
+            // Unknown countries aren't dropped since txt never has a country
+            let proxies = fetcher.try_get(FREE_LIMIT).unwrap();
+            assert_eq!(proxies.len(), FREE_LIMIT);
+            for proxy in proxies {
+                assert_eq!(proxy.country, Country::Unspecified);
+                // Anything the API filtered on gets filled in
+                assert_eq!(proxy.protocol, Protocol::Socks5);
+                assert!(proxy.supports.https);
+                assert_eq!(proxy.level, Level::Transparent);
+            }
+
+            // Raw proxies are still requested as json
+            assert_eq!(fetcher.try_get_raw_proxies(1).unwrap().len(), 1);
         }
 
         #[test]
         #[serial]
-        fn keyless() {
-            let mut fetcher = Fetcher::default();
+        fn txt_format_with_exclude_transparent() {
+            let opts = Opts::builder().format_txt(true).build();
+            let mut fetcher = Fetcher::new(opts).exclude_transparent(true);
 
-            let single = fetcher.try_get(1).unwrap();
-            let triple = fetcher.try_get(3).unwrap();
-            let the_rest = fetcher.drain();
+            // The level isn't known with txt, so nothing gets dropped for being transparent
+            assert_eq!(fetcher.try_get(FREE_LIMIT).unwrap().len(), FREE_LIMIT);
+            assert_eq!(fetcher.health().requests_made, 1);
+        }
 
-            assert_eq!(single.len(), 1);
-            assert_eq!(triple.len(), 3);
-            assert_eq!(FREE_LIMIT, single.len() + triple.len() + the_rest.len());
+        #[test]
+        #[serial]
+        fn shared_cache() {
+            use crate::proxy::test_util::proxy;
+
+            let cache = Arc::new(ValidatedCache::new(Duration::from_secs(60 * 60)));
+            let mut fetcher1 = Fetcher::default().with_cache(Arc::clone(&cache));
+            let mut fetcher2 = Fetcher::default().with_cache(Arc::clone(&cache));
+
+            let validated = proxy()
+                .with_socket("5.6.7.8:80")
+                .with_last_checked(chrono::Utc::now().naive_utc());
+            cache.insert(validated.clone());
+
+            // The cached proxy gets used first and the rest are fetched
+            let proxies = fetcher1.try_get(3).unwrap();
+            assert_eq!(proxies.len(), 3);
+            assert!(proxies.contains(&validated));
+            assert_eq!(fetcher1.drain().len(), FREE_LIMIT - 2);
+
+            // Which leaves nothing left in the cache for the other fetcher
+            assert!(cache.is_empty());
+            let proxies = fetcher2.try_get(1).unwrap();
+            assert!(!proxies.contains(&validated));
         }
 
         #[test]
         #[serial]
-        fn multiple_requests() {
-            // Multiple requests can be done with a single method call
-            for i in 0..=2 * FREE_LIMIT {
-                let mut fetcher = Fetcher::default();
-                let proxies = fetcher.try_get(i).unwrap();
-                assert_eq!(proxies.len(), i);
-            }
+        fn shared_cache_filters() {
+            use crate::proxy::test_util::proxy;
+
+            let cache = Arc::new(ValidatedCache::new(Duration::from_secs(60 * 60)));
+            let premium = |builder: OptsBuilder| builder.api_key("<key>".to_string()).build();
+            let mut germany = Fetcher::new(premium(
+                Opts::builder().countries(Countries::allow().country(Country::DE)),
+            ))
+            .with_cache(Arc::clone(&cache));
+            let mut socks = Fetcher::new(premium(Opts::builder().protocol(Protocol::Socks5)))
+                .with_cache(Arc::clone(&cache));
+
+            // A cached US http proxy matches neither fetcher
+            let now = chrono::Utc::now().naive_utc();
+            let us_http = proxy().with_socket("5.6.7.8:80").with_last_checked(now);
+            cache.insert(us_http.clone());
+            assert!(!germany.try_get(1).unwrap().contains(&us_http));
+            assert!(!socks.try_get(1).unwrap().contains(&us_http));
+            assert!(cache.contains(&us_http));
+
+            // While one that does gets handed out
+            let de_socks = us_http
+                .clone()
+                .with_socket("9.9.9.9:1080")
+                .with_country(Country::DE)
+                .with_protocol(Protocol::Socks5);
+            cache.insert(de_socks.clone());
+            assert_eq!(socks.try_get(1).unwrap(), [de_socks]);
+            assert_eq!(socks.health().requests_made, 1);
+            assert_eq!(cache.len(), 1);
+
+            // and the fetcher level filters apply too
+            let mut opaque = Fetcher::default()
+                .exclude_transparent(true)
+                .with_cache(Arc::clone(&cache));
+            let transparent = us_http.with_level(Level::Transparent);
+            cache.insert(transparent.clone());
+            assert_eq!(opaque.try_get(1).unwrap().len(), 1);
+            assert!(cache.contains(&transparent));
         }
 
         #[test]
         #[serial]
-        fn multiple_fetchers() {
-            // Each fetcher should be independent
-            let mut default = Fetcher::default();
-            let mut premium = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build());
-            let mut custom = Fetcher::new(
+        fn partition_by_health() {
+            use std::time::Duration;
+
+            let cache = Arc::new(ValidatedCache::new(Duration::from_secs(60 * 60)));
+            let mut fetcher = Fetcher::default().with_cache(Arc::clone(&cache));
+            let _ = fetcher.try_get(1).unwrap();
+
+            // Nothing has been validated yet
+            let (known_good, unvalidated) = fetcher.partition_by_health();
+            assert!(known_good.is_empty());
+            assert_eq!(unvalidated.len(), FREE_LIMIT - 1);
+
+            // Validating the (mocked) proxy marks the matching buffered ones as known-good
+            let mut validated = unvalidated[0].clone();
+            validated.last_checked = chrono::Utc::now().naive_utc();
+            cache.insert(validated);
+            let (known_good, unvalidated) = fetcher.partition_by_health();
+            assert_eq!(known_good.len(), FREE_LIMIT - 1);
+            assert!(unvalidated.is_empty());
+
+            // And everything is unvalidated without a cache
+            let mut cacheless = Fetcher::default();
+            let _ = cacheless.try_get(1).unwrap();
+            let (known_good, unvalidated) = cacheless.partition_by_health();
+            assert!(known_good.is_empty());
+            assert_eq!(unvalidated.len(), FREE_LIMIT - 1);
+        }
+    }
+
+    mod diagnostics {
+        use super::*;
+
+        #[test]
+        #[serial]
+        fn filter_compliance() {
+            use crate::types::Protocol;
+
+            // The mocked proxies are anonymous http proxies that don't support anything
+            let mut fetcher = Fetcher::new(
                 Opts::builder()
                     .level(Level::Elite)
-                    .cookies(true)
-                    .countries(Countries::allow().country(Country::CA))
+                    .protocol(Protocol::Http)
                     .build(),
             );
+            assert!(fetcher.filter_compliance().is_empty());
+            fetcher.fetch_and_store().unwrap();
 
-            let single = default.try_get(1).unwrap();
-            let double = premium.try_get(2).unwrap();
-            let triple = custom.try_get(3).unwrap();
-            assert_eq!(single.len(), 1);
-            assert_eq!(double.len(), 2);
-            assert_eq!(triple.len(), 3);
-            assert_eq!(default.drain().len(), FREE_LIMIT - single.len());
-            assert_eq!(premium.drain().len(), PREMIUM_LIMIT - double.len());
-            assert_eq!(custom.drain().len(), FREE_LIMIT - triple.len());
+            let mut expected = HashMap::new();
+            expected.insert(Filter::Level, 1.0);
+            expected.insert(Filter::Protocol, 0.0);
+            assert_eq!(fetcher.filter_compliance(), expected);
+        }
+
+        #[test]
+        #[serial]
+        fn health() {
+            let mut fetcher = Fetcher::default();
+            let report = fetcher.health();
+            assert_eq!(report.buffered, 0);
+            assert!(!report.premium);
+            assert_eq!(report.requests_made, 0);
+            assert!(report.errors_by_kind.is_empty());
+            assert_eq!(report.last_error, None);
+
+            let _ = fetcher.try_get(1).unwrap();
+            let report = fetcher.health();
+            assert_eq!(report.buffered, FREE_LIMIT - 1);
+            assert_eq!(report.requests_made, 1);
+            // We just made a request so we need to wait before the next
+            assert!(report.time_until_ready > Duration::from_secs(0));
+            assert!(report.time_until_ready <= constants::DELAY);
+
+            // The mock can't return errors so record some directly
+            fetcher
+                .stats
+                .record::<()>(&Err(ApiError::NoProxy { response: None }));
+            fetcher
+                .stats
+                .record::<()>(&Err(ApiError::NoProxy { response: None }));
+            fetcher
+                .stats
+                .record::<()>(&Err(ApiError::DailyLimit { response: None }));
+            let report = fetcher.health();
+            assert_eq!(report.requests_made, 4);
+            assert_eq!(report.errors_by_kind.get("NoProxy"), Some(&2));
+            assert_eq!(report.errors_by_kind.get("DailyLimit"), Some(&1));
+            assert_eq!(
+                report.last_error,
+                Some(ApiError::DailyLimit { response: None }.to_string())
+            );
+
+            // Premium fetchers never have to wait
+            let premium = Fetcher::new(Opts::builder().api_key("<key>".to_string()).build());
+            let report = premium.health();
+            assert!(report.premium);
+            assert_eq!(report.time_until_ready, Duration::from_secs(0));
         }
     }
 
     mod delays {
-        use std::time::Duration;
-
         use super::*;
 
         const TEN_MILLISEC: Duration = Duration::from_millis(10);
 
         // TODO: do this with a fixture
         fn reset_last_fetched() {
-            let mut last_fetched = LAST_FETCHED.lock().unwrap();
-            *last_fetched = Instant::now() - constants::DELAY;
+            *THROTTLE.lock().unwrap() = Throttle::new();
         }
 
         // Helper function for ensuring runtime of a `FnOnce`
@@ -299,6 +3041,102 @@ mod tests {
             result
         }
 
+        #[test]
+        #[serial]
+        fn time_until_ready() {
+            reset_last_fetched();
+            let mut fetcher = Fetcher::default();
+            assert_eq!(fetcher.time_until_ready(), Duration::from_secs(0));
+
+            let _ = fetcher.try_get(1).unwrap();
+            let until_ready = fetcher.time_until_ready();
+            assert!(until_ready > constants::DELAY - TEN_MILLISEC);
+            assert!(until_ready <= constants::DELAY);
+            // Checking doesn't reset anything
+            assert!(fetcher.time_until_ready() <= until_ready);
+
+            // Which lines up with how long the next request actually waits
+            let _ = time_it(
+                || fetcher.try_get(FREE_LIMIT).unwrap(),
+                (until_ready, TEN_MILLISEC),
+            );
+
+            // Other fetchers share the same delay
+            assert!(Fetcher::default().time_until_ready() > Duration::from_secs(0));
+            thread::sleep(constants::DELAY);
+            assert_eq!(fetcher.time_until_ready(), Duration::from_secs(0));
+        }
+
+        #[test]
+        #[serial]
+        fn prefetcher() {
+            reset_last_fetched();
+            let mut prefetcher = Fetcher::default().spawn_prefetcher(2 * FREE_LIMIT);
+
+            // Give it time to fill the buffer with two requests
+            thread::sleep(constants::DELAY + 5 * TEN_MILLISEC);
+            let proxies = time_it(
+                || prefetcher.try_get(2 * FREE_LIMIT).unwrap(),
+                (TEN_MILLISEC, TEN_MILLISEC),
+            );
+            assert_eq!(proxies.len(), 2 * FREE_LIMIT);
+
+            let fetcher = prefetcher.shutdown();
+            assert_eq!(fetcher.health().requests_made, 2);
+        }
+
+        #[test]
+        #[serial]
+        fn prefetcher_errors() {
+            reset_last_fetched();
+            let mut prefetcher = Fetcher::default()
+                .with_transport(FailFirst::new(1))
+                .spawn_prefetcher(FREE_LIMIT);
+            thread::sleep(5 * TEN_MILLISEC);
+
+            // The error from prefetching gets passed along
+            let result = prefetcher.try_get(1);
+            assert!(matches!(result, Err(ApiError::Server { status: 500, .. })));
+
+            // and since it can clear up on its own prefetching carries on
+            assert_eq!(prefetcher.try_get(1).unwrap().len(), 1);
+            let fetcher = prefetcher.shutdown();
+            assert_eq!(fetcher.health().requests_made, 2);
+            assert_eq!(fetcher.buffered(), FREE_LIMIT - 1);
+        }
+
+        #[test]
+        #[serial]
+        fn multiple_protocols() {
+            use crate::types::Protocol;
+
+            reset_last_fetched();
+            let opts = Opts::builder()
+                .protocols(&[Protocol::Socks4, Protocol::Socks5])
+                .build();
+            let mut fetcher = Fetcher::new(opts);
+
+            // Each protocol is its own request, and those requests are still delayed
+            let proxies = time_it(
+                || fetcher.try_get(2 * FREE_LIMIT).unwrap(),
+                (constants::DELAY, TEN_MILLISEC),
+            );
+            assert_eq!(fetcher.health().requests_made, 2);
+            let socks4 = proxies
+                .iter()
+                .filter(|proxy| proxy.protocol == Protocol::Socks4)
+                .count();
+            let socks5 = proxies
+                .iter()
+                .filter(|proxy| proxy.protocol == Protocol::Socks5)
+                .count();
+            assert_eq!((socks4, socks5), (FREE_LIMIT, FREE_LIMIT));
+
+            // And then it wraps back around
+            let proxies = fetcher.try_get(1).unwrap();
+            assert_eq!(proxies[0].protocol, Protocol::Socks4);
+        }
+
         #[test]
         #[serial]
         fn single_fetcher() {
@@ -359,6 +3197,40 @@ mod tests {
             );
         }
 
+        #[test]
+        #[serial]
+        fn independent_rate_limit() {
+            let independent = || Fetcher::new(Opts::builder().independent_rate_limit(true).build());
+
+            // Independent fetchers don't wait on the shared delay or each other
+            let (mut fetcher1, fetcher2) = time_it(
+                || {
+                    reset_last_fetched();
+
+                    let _ = Fetcher::default().try_get(1).unwrap();
+                    let mut fetcher1 = independent();
+                    let mut fetcher2 = independent();
+                    let _ = fetcher1.try_get(1).unwrap();
+                    let _ = fetcher2.try_get(1).unwrap();
+
+                    (fetcher1, fetcher2)
+                },
+                // 10ms +/- 10ms
+                (TEN_MILLISEC, TEN_MILLISEC),
+            );
+
+            // But they still delay between their own requests, including from clones
+            assert!(fetcher2.time_until_ready() > Duration::from_secs(0));
+            let mut clone = fetcher1.clone();
+            time_it(
+                || {
+                    let _ = fetcher1.try_get(FREE_LIMIT).unwrap();
+                    let _ = clone.try_get(FREE_LIMIT + 1).unwrap();
+                },
+                (2 * constants::DELAY, TEN_MILLISEC),
+            );
+        }
+
         #[test]
         #[serial]
         fn multiple_fetchers() {
@@ -419,5 +3291,91 @@ mod tests {
                 (constants::DELAY, TEN_MILLISEC),
             );
         }
+
+        #[test]
+        #[serial]
+        fn custom_delays() {
+            let fetcher_with_delay =
+                |delay| Fetcher::new(Opts::builder().min_request_delay(delay).build());
+
+            // A shorter delay is used when it's the only one
+            time_it(
+                || {
+                    reset_last_fetched();
+                    let mut fetcher = fetcher_with_delay(constants::DELAY / 2);
+                    let _ = fetcher.try_get(FREE_LIMIT + 1);
+                },
+                // delay / 2 +/- 10ms
+                (constants::DELAY / 2, TEN_MILLISEC),
+            );
+
+            // But a longer delay from the previous request still has to be waited out
+            time_it(
+                || {
+                    reset_last_fetched();
+                    let mut slow = fetcher_with_delay(constants::DELAY * 2);
+                    let mut fast = fetcher_with_delay(constants::DELAY / 2);
+                    let _ = slow.try_get(1);
+                    let _ = fast.try_get(FREE_LIMIT + 1);
+                },
+                // The reset only covers one delay, and after waiting out the slow delay the fast
+                // one applies again, so (1 + 2 + 1 / 2) * delay +/- 10ms
+                (constants::DELAY * 7 / 2, TEN_MILLISEC),
+            );
+        }
+
+        fn poison_last_fetched() {
+            struct Panicking;
+
+            impl Transport for Panicking {
+                fn call(&self, _request: ureq::Request) -> Result<NaiveResponse, ApiError> {
+                    panic!("Poisoning the lock on purpose");
+                }
+            }
+
+            // Panicking mid-request poisons the lock that the fetcher is holding
+            let _ = thread::spawn(|| {
+                let mut fetcher = Fetcher::default().with_transport(Panicking);
+                let _ = fetcher.try_get(1);
+            })
+            .join();
+            assert!(THROTTLE.is_poisoned());
+        }
+
+        #[test]
+        #[serial]
+        fn poison_reset() {
+            reset_last_fetched();
+            poison_last_fetched();
+
+            // Resetting the timer means waiting out a full delay
+            time_it(
+                || {
+                    let mut fetcher = Fetcher::default().poison_policy(PoisonPolicy::Reset);
+                    let _ = fetcher.try_get(1);
+                },
+                // delay +/- 10ms
+                (constants::DELAY, TEN_MILLISEC),
+            );
+            assert!(!THROTTLE.is_poisoned());
+        }
+
+        #[test]
+        #[serial]
+        fn poison_proceed() {
+            reset_last_fetched();
+            poison_last_fetched();
+
+            // The old timer was already expired so there's no delay
+            time_it(
+                || {
+                    let mut fetcher = Fetcher::default().poison_policy(PoisonPolicy::Proceed);
+                    let _ = fetcher.try_get(1);
+                },
+                // 10ms +/- 10ms
+                (TEN_MILLISEC, TEN_MILLISEC),
+            );
+            assert!(!THROTTLE.is_poisoned());
+        }
     }
 }
@@ -0,0 +1,281 @@
+//! [`AsyncFetcher`][AsyncFetcher] is the async counterpart to [`Fetcher`][super::Fetcher] for use
+//! within an async runtime like tokio.
+//!
+//! This module is only available with the `async` feature enabled.
+
+use std::{collections::HashSet, net::SocketAddr, time::Instant};
+
+use super::{log_outcome, parse_response, redacted_query, Throttle};
+use crate::{
+    constants,
+    errors::ApiError,
+    opts::{Format, Opts},
+    proxy::{proxies_from_json, proxies_from_txt, Proxy},
+    types::NaiveResponse,
+};
+
+use tokio::{sync::Mutex, time};
+
+lazy_static! {
+    // Async fetchers can't share the blocking mutex since the lock is held while waiting
+    static ref THROTTLE: Mutex<Throttle> = Mutex::new(Throttle::new());
+}
+
+/// The async entrypoint into the API.
+///
+/// An `AsyncFetcher` works like a [`Fetcher`][super::Fetcher] except that requests and delays
+/// never block the thread, so it's safe to use from within an async runtime. Delays are coordinated
+/// between every keyless `AsyncFetcher` including across tasks, but not with blocking `Fetcher`s,
+/// so avoid using both at once without an API key.
+///
+/// ```no_run
+/// use lead_oxide::fetcher::async_fetcher::AsyncFetcher;
+///
+/// # async fn run() {
+/// let mut fetcher = AsyncFetcher::default();
+/// let proxies = fetcher.try_get(10).await.unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AsyncFetcher {
+    opts: Opts,
+    client: reqwest::Client,
+    current_protocol: usize,
+    proxies: Vec<Proxy>,
+    seen: HashSet<SocketAddr>,
+}
+
+impl AsyncFetcher {
+    /// Creates a new `AsyncFetcher` with a given set of `Opts`.
+    pub fn new(opts: Opts) -> Self {
+        Self {
+            opts,
+            client: reqwest::Client::new(),
+            current_protocol: 0,
+            proxies: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Attempts to get the specified amount of proxies from the API, returning an error if there
+    /// was an issue with the API. Like [`Fetcher::try_get`][super::Fetcher::try_get] any proxies
+    /// returned before an error was encountered are still stored in the internal list.
+    pub async fn try_get(&mut self, amount: usize) -> Result<Vec<Proxy>, ApiError> {
+        if self.proxies.len() < amount {
+            if self.opts.is_premium() {
+                // No delays are needed with an API key
+                let mut empty_fetches = 0;
+                while self.proxies.len() < amount {
+                    self.fetch_and_store(&mut empty_fetches).await?;
+                }
+            } else {
+                // The lock is held across the whole loop so concurrent tasks take turns
+                let mut throttle = THROTTLE.lock().await;
+                let mut empty_fetches = 0;
+                while self.proxies.len() < amount {
+                    // Delay to prevent rate limiting
+                    let delay = throttle.delay_for(&self.opts);
+                    let delta = Instant::now().duration_since(throttle.last_fetched);
+                    if delta < delay {
+                        trace!("Waiting {:?} to avoid the rate limit", delay - delta);
+                        time::sleep(delay - delta).await;
+                    }
+
+                    let fetched = self.fetch_and_store(&mut empty_fetches).await;
+
+                    // Update the request time
+                    throttle.fetched(&self.opts);
+                    fetched?;
+                }
+            }
+        }
+
+        Ok(self.proxies.split_off(self.proxies.len() - amount))
+    }
+
+    /// Consumes the `AsyncFetcher` returning any proxies still left in the internal list.
+    pub fn drain(self) -> Vec<Proxy> {
+        self.proxies
+    }
+
+    // Gives up once too many requests in a row are entirely filtered out like the blocking
+    // `Fetcher`
+    async fn fetch_and_store(&mut self, empty_fetches: &mut usize) -> Result<(), ApiError> {
+        let mut proxies = self.fetch().await?;
+
+        // Apply any filtering that the API can't do for us
+        let now = chrono::Utc::now().naive_utc();
+        proxies.retain(|proxy| self.opts.allows(proxy, now));
+        if self.opts.dedupe {
+            let seen = &mut self.seen;
+            proxies.retain(|proxy| seen.insert(proxy.socket));
+        }
+
+        if proxies.is_empty() {
+            *empty_fetches += 1;
+            if *empty_fetches >= constants::MAX_EMPTY_FETCHES {
+                return Err(ApiError::NoProxy { response: None });
+            }
+        } else {
+            *empty_fetches = 0;
+        }
+
+        self.proxies.append(&mut proxies);
+        Ok(())
+    }
+
+    async fn fetch(&mut self) -> Result<Vec<Proxy>, ApiError> {
+        let opts = self.opts.next_request(&mut self.current_protocol);
+        debug!("Requesting proxies with `{}`", redacted_query(&opts));
+        let naive_resp = send(&self.client, &opts).await?;
+        let result = match opts.format {
+            Format::Json => {
+                let keep_unknown = opts.keep_unknown_countries;
+                parse_response(naive_resp, |json| proxies_from_json(json, keep_unknown))
+            }
+            Format::Txt => parse_response(naive_resp, proxies_from_txt).map(|mut proxies| {
+                for proxy in &mut proxies {
+                    opts.fill_known(proxy);
+                }
+                proxies
+            }),
+        };
+        log_outcome(&result);
+
+        result
+    }
+}
+
+impl Default for AsyncFetcher {
+    fn default() -> Self {
+        Self::new(Opts::default())
+    }
+}
+
+/// Internal
+#[cfg(not(test))]
+async fn send(client: &reqwest::Client, opts: &Opts) -> Result<NaiveResponse, ApiError> {
+    use super::query_params;
+
+    let url = format!("{}{}", opts.api_base(), query_params(opts));
+    let mut request = client
+        .get(url)
+        .header(reqwest::header::USER_AGENT, opts.user_agent());
+    if let Some(timeout) = opts.request_timeout {
+        request = request.timeout(timeout);
+    }
+
+    // Like `UreqTransport`, only failing to get any response at all is a transport error
+    let resp = request.send().await.map_err(|err| ApiError::Transport {
+        message: err.to_string(),
+    })?;
+    let status = resp.status().as_u16();
+    let text = resp.text().await.unwrap_or_default();
+
+    Ok(NaiveResponse::new(status, text))
+}
+
+/// Internal
+// Tests never touch the real API
+#[cfg(test)]
+async fn send(_client: &reqwest::Client, opts: &Opts) -> Result<NaiveResponse, ApiError> {
+    use super::{tests::MockTransport, transport::Transport, Fetcher};
+
+    MockTransport.call(Fetcher::request_builder(opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    use std::time::Duration;
+
+    const FREE_LIMIT: usize = 5;
+    const TEN_MILLISEC: Duration = Duration::from_millis(10);
+
+    async fn reset_last_fetched() {
+        *THROTTLE.lock().await = Throttle::new();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn keyless() {
+        reset_last_fetched().await;
+        let mut fetcher = AsyncFetcher::default();
+
+        let proxies = fetcher.try_get(FREE_LIMIT + 1).await.unwrap();
+        assert_eq!(proxies.len(), FREE_LIMIT + 1);
+        assert_eq!(fetcher.drain().len(), FREE_LIMIT - 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn coordinated_delays() {
+        reset_last_fetched().await;
+        let mut fetcher1 = AsyncFetcher::default();
+        let mut fetcher2 = AsyncFetcher::default();
+
+        // Two requests from different fetchers are still one delay apart without blocking other
+        // tasks on the same thread while waiting
+        let start = Instant::now();
+        let (proxies1, proxies2, waited) =
+            tokio::join!(fetcher1.try_get(1), fetcher2.try_get(1), async {
+                time::sleep(TEN_MILLISEC).await;
+                start.elapsed()
+            });
+        let elapsed = start.elapsed();
+
+        assert_eq!(proxies1.unwrap().len() + proxies2.unwrap().len(), 2);
+        assert!(elapsed >= constants::DELAY - TEN_MILLISEC, "Too fast");
+        assert!(elapsed <= constants::DELAY + TEN_MILLISEC, "Too slow");
+        assert!(waited < constants::DELAY / 2, "Blocked the runtime");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn premium() {
+        let mut fetcher = AsyncFetcher::new(Opts::builder().api_key("<key>".to_string()).build());
+
+        // Premium fetchers don't delay between requests
+        let start = Instant::now();
+        let _ = fetcher.try_get(1).await.unwrap();
+        let _ = fetcher.try_get(50).await.unwrap();
+        assert!(start.elapsed() <= TEN_MILLISEC);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn txt() {
+        let opts = Opts::builder()
+            .api_key("<key>".to_string())
+            .format_txt(true)
+            .build();
+        let mut fetcher = AsyncFetcher::new(opts);
+
+        // Everything gets parsed from the txt format the same as a blocking `Fetcher`
+        let proxies = fetcher.try_get(3).await.unwrap();
+        assert_eq!(proxies.len(), 3);
+        assert!(proxies
+            .iter()
+            .all(|proxy| proxy.socket == "1.2.3.4:4321".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn dedupe() {
+        let opts = Opts::builder()
+            .api_key("<key>".to_string())
+            .dedupe(true)
+            .build();
+        let mut fetcher = AsyncFetcher::new(opts);
+
+        // The mock always returns the same socket so everything after the first is a duplicate
+        let _ = fetcher.try_get(1).await.unwrap();
+        assert!(matches!(
+            fetcher.try_get(2).await,
+            Err(ApiError::NoProxy { .. })
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+//! [`Transport`][Transport] is how a [`Fetcher`][super::Fetcher] sends its requests to the API.
+//!
+//! The default [`UreqTransport`][UreqTransport] just sends the request, but providing your own
+//! through [`Fetcher::with_transport`][super::Fetcher::with_transport] allows for things like
+//! serving canned responses in tests or recording every request made.
+
+use std::time::Duration;
+
+use crate::{errors::ApiError, types::NaiveResponse};
+
+/// Re-exported so that implementing [`Transport`][Transport] doesn't need a matching `ureq`
+/// dependency.
+pub use ureq::Request;
+
+/// Sends a fully built request to the API and returns the response.
+///
+/// Any response is returned as is and gets interpreted by the `Fetcher`, so an implementation only
+/// needs to return an error when no response was received at all, which is typically
+/// `ApiError::Transport`.
+///
+/// ```
+/// use lead_oxide::{
+///     errors::ApiError,
+///     fetcher::{
+///         transport::{Request, Transport},
+///         Fetcher,
+///     },
+///     types::NaiveResponse,
+/// };
+///
+/// // Pretends that the API has run out of proxies
+/// struct NoProxies;
+///
+/// impl Transport for NoProxies {
+///     fn call(&self, _request: Request) -> Result<NaiveResponse, ApiError> {
+///         Ok(NaiveResponse::new(200, "No proxy".to_string()))
+///     }
+/// }
+///
+/// let mut fetcher = Fetcher::default().with_transport(NoProxies);
+/// assert!(matches!(fetcher.try_get(1), Err(ApiError::NoProxy { .. })));
+/// ```
+pub trait Transport: Send + Sync {
+    fn call(&self, request: Request) -> Result<NaiveResponse, ApiError>;
+}
+
+/// The default [`Transport`][Transport] which sends requests with `ureq`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UreqTransport;
+
+impl Transport for UreqTransport {
+    // `ureq` reports transport errors as synthetic responses which shouldn't be mistaken for the
+    // API's
+    fn call(&self, mut request: Request) -> Result<NaiveResponse, ApiError> {
+        let resp = request.call();
+        if resp.synthetic() {
+            let message = resp
+                .into_synthetic_error()
+                .map(|err| err.to_string())
+                .unwrap_or_default();
+            Err(ApiError::Transport { message })
+        } else {
+            Ok(NaiveResponse::from(resp))
+        }
+    }
+}
+
+/// Internal
+pub(crate) fn set_timeouts(request: &mut Request, timeout: Duration) {
+    // A timeout of 0 means no timeout for `ureq`
+    let millis = (timeout.as_millis() as u64).max(1);
+    request.timeout_connect(millis).timeout_read(millis);
+}
@@ -25,16 +25,15 @@
 //! quite severe (will deny requests for potentially several hours), so it's best to avoid by all
 //! means possible.
 //!
+//! Enabling the `log` feature logs each request along with its query (API key redacted), how many
+//! proxies came back, how any error was classified, and every delay through the `log` crate, which
+//! helps with tracking down where rate limiting is coming from.
+//!
 //! ## Quickstart
 //!
 //! ```rust,no_run
 //! use iso_country::Country;
-//! use lead_oxide::{
-//!     errors::ApiError,
-//!     fetcher::Fetcher,
-//!     opts::Opts,
-//!     types::{Countries, Level, Protocol, TimeToConnect},
-//! };
+//! use lead_oxide::prelude::*;
 //!
 //! use std::{convert::TryFrom, time::Duration};
 //!
@@ -72,9 +71,47 @@
 #[macro_use]
 extern crate lazy_static;
 
+// Logging goes through these so that nothing gets logged without the `log` feature. The arguments
+// are still type checked when it's off, but never evaluated
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        log::debug!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        log::trace!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+pub mod cache;
 mod constants;
 pub mod errors;
 pub mod fetcher;
 pub mod opts;
+pub mod prelude;
 pub mod proxy;
 pub mod types;
+
+pub use fetcher::{configure_shared, shared};
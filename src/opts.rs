@@ -1,10 +1,17 @@
 //! [`Opts`][Opts] provide the ability to filter the returned proxies.
 
-use std::num::NonZeroU16;
+use std::{convert::TryFrom, mem, num::NonZeroU16, ops::RangeInclusive, time::Duration};
 
-use crate::types::{Countries, LastChecked, Level, Protocol, TimeToConnect};
+use crate::{
+    constants,
+    errors::{OptsError, ParamError},
+    proxy::Proxy,
+    types::{Countries, LastChecked, Level, Protocol, TimeToConnect},
+};
 
-use serde::Serialize;
+use chrono::NaiveDateTime;
+use iso_country::Country;
+use serde::{de::Error as _, Deserialize, Serialize};
 use serde_repr::Serialize_repr;
 
 // TODO: allow for multiple things being specified on the different things that accept it?
@@ -12,21 +19,102 @@ use serde_repr::Serialize_repr;
 ///
 /// Constructed with `Opts::builder()`. By default any field that isn't specified will just return
 /// any possible value so these options just constrain the returned results.
-#[derive(Clone, Debug, Default, PartialEq)]
+///
+/// `OptsBuilder` can also be (de)serialized to store setups in a config file. This uses the same
+/// names as the builder methods instead of the API's parameter names, where durations are stored
+/// in seconds with a `_secs` suffix and countries are stored as either an `allow` or `block` list.
+///
+/// ```
+/// use lead_oxide::opts::OptsBuilder;
+///
+/// let config = r#"{
+///     "level": "elite",
+///     "protocols": ["socks4", "socks5"],
+///     "countries": { "block": ["CN", "RU"] },
+///     "max_age_secs": 1800,
+///     "https": true
+/// }"#;
+/// let opts = serde_json::from_str::<OptsBuilder>(config).unwrap().build();
+/// ```
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
 pub struct OptsBuilder {
+    #[serde(skip_serializing_if = "Option::is_none")]
     api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     level: Option<Level>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     protocol: Option<Protocol>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    protocols: Vec<Protocol>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "config::countries")]
     countries: Option<Countries>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    excluded_countries: Vec<Country>,
+    #[serde(
+        rename = "last_checked_secs",
+        skip_serializing_if = "Option::is_none",
+        with = "config::last_checked"
+    )]
     last_checked: Option<LastChecked>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     port: Option<NonZeroU16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port_range: Option<RangeInclusive<u16>>,
+    #[serde(
+        rename = "time_to_connect_secs",
+        skip_serializing_if = "Option::is_none",
+        with = "config::time_to_connect"
+    )]
     time_to_connect: Option<TimeToConnect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     cookies: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     connects_to_google: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     https: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     post: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     referer: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     forwards_user_agent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_unknown_countries: Option<bool>,
+    #[serde(
+        rename = "max_age_secs",
+        skip_serializing_if = "Option::is_none",
+        with = "config::secs"
+    )]
+    max_age: Option<Duration>,
+    #[serde(
+        rename = "max_connect_client_side_secs",
+        skip_serializing_if = "Option::is_none",
+        with = "config::secs"
+    )]
+    max_connect_client_side: Option<Duration>,
+    #[serde(
+        rename = "min_request_delay_secs",
+        skip_serializing_if = "Option::is_none",
+        with = "config::secs"
+    )]
+    min_request_delay: Option<Duration>,
+    #[serde(
+        rename = "request_timeout_secs",
+        skip_serializing_if = "Option::is_none",
+        with = "config::secs"
+    )]
+    request_timeout: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_base: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dedupe: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    independent_rate_limit: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format_txt: Option<bool>,
 }
 
 impl OptsBuilder {
@@ -37,15 +125,65 @@ impl OptsBuilder {
     }
 
     /// The anonymity level of proxies returned by the API where the proxies are either Anonymous or
-    /// Elite (Transparent isn't provided).
+    /// Elite. The API can't filter on Transparent proxies, so `Level::Transparent` is handled by the
+    /// library instead by dropping any other level from unfiltered responses. Use
+    /// [`Fetcher::exclude_transparent`][crate::fetcher::Fetcher::exclude_transparent] to drop
+    /// transparent proxies instead.
     pub fn level(mut self, level: Level) -> Self {
         self.level = Some(level);
         self
     }
 
+    /// Unsets the [`level`][OptsBuilder::level] so any level is allowed again. This is handy for
+    /// relaxing a clone of a shared base builder.
+    ///
+    /// ```
+    /// use lead_oxide::{opts::Opts, types::Level};
+    ///
+    /// let base = Opts::builder().level(Level::Elite).https(true);
+    /// let relaxed = base.clone().any_level();
+    /// assert_eq!(relaxed, Opts::builder().https(true));
+    /// ```
+    pub fn any_level(mut self) -> Self {
+        self.level = None;
+        self
+    }
+
     /// The protocol supported by the proxies. This can either be HTTP, SOCKS4, or SOCKS5.
     pub fn protocol(mut self, protocol: Protocol) -> Self {
         self.protocol = Some(protocol);
+        self.protocols.clear();
+        self
+    }
+
+    /// Allows any of the listed protocols. The API only filters on a single protocol, so when more
+    /// than one is listed the [`Fetcher`][crate::fetcher::Fetcher] takes turns requesting each
+    /// protocol and merges the results. Every request is still delayed like normal. An empty list
+    /// leaves the protocol unfiltered.
+    ///
+    /// ```
+    /// use lead_oxide::{opts::Opts, types::Protocol};
+    ///
+    /// let socks_only = Opts::builder()
+    ///     .protocols(&[Protocol::Socks4, Protocol::Socks5])
+    ///     .build();
+    /// ```
+    pub fn protocols(mut self, protocols: &[Protocol]) -> Self {
+        self.protocol = None;
+        self.protocols.clear();
+        for &protocol in protocols {
+            if !self.protocols.contains(&protocol) {
+                self.protocols.push(protocol);
+            }
+        }
+        self
+    }
+
+    /// Unsets both the [`protocol`][OptsBuilder::protocol] and
+    /// [`protocols`][OptsBuilder::protocols] so any protocol is allowed again.
+    pub fn any_protocol(mut self) -> Self {
+        self.protocol = None;
+        self.protocols.clear();
         self
     }
 
@@ -55,6 +193,44 @@ impl OptsBuilder {
         self
     }
 
+    /// Unsets the [`countries`][OptsBuilder::countries] so proxies from any country are allowed
+    /// again.
+    pub fn any_countries(mut self) -> Self {
+        self.countries = None;
+        self.excluded_countries.clear();
+        self
+    }
+
+    /// Drops proxies from any of `countries` after fetching. The API can only take either an
+    /// allowlist or a blocklist through [`countries`][OptsBuilder::countries], so this is how to
+    /// layer a blocklist on top of an allowlist, like allowing a handful of countries while never
+    /// using one specific country within them. Calling this again adds to the excluded countries.
+    ///
+    /// This is handled entirely by the library, so every proxy that gets dropped is one less per
+    /// request and getting the same amount of proxies can take more requests (and more of the
+    /// daily limit without an API key). Prefer a blocklist in `countries` when there's no
+    /// allowlist. Unlike the API's filters this is never dropped by
+    /// [`Fetcher::try_get_adaptive`][crate::fetcher::Fetcher::try_get_adaptive].
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::{opts::Opts, types::Countries};
+    ///
+    /// // North America, but never the US
+    /// let opts = Opts::builder()
+    ///     .countries(Countries::allow().countries(&[Country::CA, Country::MX, Country::US]))
+    ///     .exclude_countries(&[Country::US])
+    ///     .build();
+    /// ```
+    pub fn exclude_countries(mut self, countries: &[Country]) -> Self {
+        for country in countries {
+            if !self.excluded_countries.contains(country) {
+                self.excluded_countries.push(*country);
+            }
+        }
+        self
+    }
+
     /// Time when the proxies were last checked. Resolution down to minutes with a valid range of
     /// 1 to 1,000 minutes.
     pub fn last_checked(mut self, last_checked: LastChecked) -> Self {
@@ -65,6 +241,50 @@ impl OptsBuilder {
     /// Specifies the port that the proxy exposes.
     pub fn port(mut self, port: NonZeroU16) -> Self {
         self.port = Some(port);
+        self.port_range = None;
+        self
+    }
+
+    /// The same as [`port`][OptsBuilder::port], but takes a plain `u16` and errors on 0 instead of
+    /// needing a `NonZeroU16`. This is handy for ports coming from user input.
+    ///
+    /// ```
+    /// use lead_oxide::opts::Opts;
+    ///
+    /// let opts = Opts::builder().port_u16(8080).unwrap().build();
+    ///
+    /// let err = Opts::builder().port_u16(0).unwrap_err();
+    /// assert_eq!(err.to_string(), "'0' is outside bounds: 1 to 65535");
+    /// ```
+    pub fn port_u16(self, port: u16) -> Result<Self, ParamError<u16>> {
+        match NonZeroU16::new(port) {
+            Some(port) => Ok(self.port(port)),
+            None => Err(ParamError::out_of_bounds(port, (1, u16::MAX))),
+        }
+    }
+
+    /// Unsets both the [`port`][OptsBuilder::port] and [`port_range`][OptsBuilder::port_range] so
+    /// any port is allowed again.
+    pub fn any_port(mut self) -> Self {
+        self.port = None;
+        self.port_range = None;
+        self
+    }
+
+    /// Allows any port within `range`. The API only filters on a single port, so this is handled
+    /// by the library instead by fetching proxies with any port and dropping the ones outside of
+    /// `range`. This means that it can take many more requests (and more of the daily limit) to
+    /// get the same amount of proxies, especially for narrow ranges. A range of a single port is
+    /// the same as using [`port`][OptsBuilder::port].
+    ///
+    /// ```
+    /// use lead_oxide::opts::Opts;
+    ///
+    /// let opts = Opts::builder().port_range(8000..=9000).build();
+    /// ```
+    pub fn port_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.port = None;
+        self.port_range = Some(range);
         self
     }
 
@@ -112,38 +332,346 @@ impl OptsBuilder {
         self
     }
 
+    /// The maximum age of the returned proxies based on when they were last checked. Unlike
+    /// [`last_checked`][OptsBuilder::last_checked] this isn't limited to the API's range of 1 to 60
+    /// minutes since the filtering is done by the library after fetching. When the age does fall
+    /// within the API's range (and `last_checked` isn't set) the API's filter is also set to
+    /// avoid fetching proxies that would just be thrown away.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// The maximum time to connect of the returned proxies. Unlike
+    /// [`time_to_connect`][OptsBuilder::time_to_connect] this isn't limited to the API's whole
+    /// seconds from 1 to 60 since the filtering is done by the library after fetching, so
+    /// sub-second thresholds work. When the threshold rounded up to a whole second falls within
+    /// the API's range (and `time_to_connect` isn't set) the API's filter is also set to avoid
+    /// fetching proxies that would just be thrown away.
+    ///
+    /// ```
+    /// use lead_oxide::opts::Opts;
+    /// use std::time::Duration;
+    ///
+    /// let opts = Opts::builder()
+    ///     .max_connect_client_side(Duration::from_millis(500))
+    ///     .build();
+    /// ```
+    pub fn max_connect_client_side(mut self, max_connect: Duration) -> Self {
+        self.max_connect_client_side = Some(max_connect);
+        self
+    }
+
+    /// If proxies with a country that couldn't be parsed should be kept instead of dropped. The
+    /// API occasionally returns invalid country codes (around 10% of results) which get parsed as
+    /// `Country::Unspecified`. These are dropped by default to play it safe with country filters,
+    /// but if you don't care about location then keeping them increases the yield per request.
+    /// This is handled entirely by the library and isn't passed on to the API.
+    pub fn keep_unknown_countries(mut self, keep_unknown_countries: bool) -> Self {
+        self.keep_unknown_countries = Some(keep_unknown_countries);
+        self
+    }
+
+    /// Overrides the delay between requests made without an API key, which defaults to
+    /// [`RATE_LIMIT_DELAY`][crate::fetcher::RATE_LIMIT_DELAY]. Delays are coordinated across every
+    /// keyless [`Fetcher`][crate::fetcher::Fetcher], so each request waits out either this delay or
+    /// the delay of whichever `Fetcher` made the previous request, whichever is longer. This is
+    /// ignored when using an API key since there is no rate limit.
+    pub fn min_request_delay(mut self, delay: Duration) -> Self {
+        self.min_request_delay = Some(delay);
+        self
+    }
+
+    /// How long to wait on the API while connecting and on each read before giving up with an
+    /// `ApiError::Transport`. By default a request can wait forever on an unresponsive connection.
+    /// This is handled entirely by the library and isn't passed on to the API.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// The `User-Agent` header sent with each request to pubproxy.com, which defaults to
+    /// `lead-oxide/<version>`. This only identifies the client talking to the API and has nothing
+    /// to do with the [`forwards_user_agent`][OptsBuilder::forwards_user_agent] attribute of the
+    /// returned proxies.
+    pub fn user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = Some(user_agent);
+        self
+    }
+
+    /// Sends requests to `api_base` instead of pubproxy.com, like a local mock server or a
+    /// compatible mirror. The query parameters get appended to the URL, so a trailing `?` (or `&`
+    /// if the URL already has a query) is added if it's missing.
+    ///
+    /// ```
+    /// use lead_oxide::opts::Opts;
+    ///
+    /// let opts = Opts::builder()
+    ///     .api_base("http://localhost:8080/api/proxy".to_string())
+    ///     .try_build()
+    ///     .unwrap();
+    /// ```
+    pub fn api_base(mut self, api_base: String) -> Self {
+        self.api_base = Some(api_base);
+        self
+    }
+
+    /// If a [`Fetcher`][crate::fetcher::Fetcher] should drop any proxies with a socket it has
+    /// already fetched before. The API can return the same proxies across back-to-back requests,
+    /// so this keeps `try_get` from handing out duplicates. Note that enabling this can increase
+    /// the number of requests made since duplicates don't count towards the amount requested, and
+    /// `try_get` returns `ApiError::NoProxy` once several requests in a row are all duplicates.
+    pub fn dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = Some(dedupe);
+        self
+    }
+
+    /// Gives a keyless [`Fetcher`][crate::fetcher::Fetcher] its own delay between requests instead
+    /// of sharing one with every other keyless `Fetcher` in the process. Clones of the `Fetcher`
+    /// still share its delay.
+    ///
+    /// **Be careful with this.** The API rate limits by IP, so using this while anything else is
+    /// making keyless requests from the same machine (including other libraries that use
+    /// `lead-oxide`, or other programs) can easily trigger `ApiError::RateLimit`, which can deny
+    /// requests for hours. Only use this when you know this `Fetcher` is the only thing using the
+    /// API, or when the `Fetcher`s are talking to separate mirrors through
+    /// [`api_base`][OptsBuilder::api_base].
+    pub fn independent_rate_limit(mut self, independent: bool) -> Self {
+        self.independent_rate_limit = Some(independent);
+        self
+    }
+
+    /// Requests the API's lighter `txt` format instead of json. The `txt` format only includes the
+    /// socket of each proxy, so the resulting [`Proxy`][crate::proxy::Proxy]s fill in what they can
+    /// from these `Opts` like the level and protocol, and fall back to defaults for the rest (see
+    /// [`proxies_from_txt`][crate::proxy::proxies_from_txt]). Since the country is unknown these
+    /// proxies are never dropped for having an unknown country.
+    ///
+    /// The same goes for the age and time to connect, so [`max_age`][OptsBuilder::max_age] and
+    /// [`max_connect_client_side`][OptsBuilder::max_connect_client_side] never drop these proxies.
+    /// Only the part of those filters the API handles applies, which is when they fall within the
+    /// API's range.
+    pub fn format_txt(mut self, format_txt: bool) -> Self {
+        self.format_txt = Some(format_txt);
+        self
+    }
+
+    /// Cuts down on the size of each response by requesting the `txt` format, which is handy for
+    /// high-volume premium use. This is the same as [`format_txt`][OptsBuilder::format_txt], so
+    /// only the `socket` and `protocol` of each proxy are populated (the protocol from the line's
+    /// scheme when there is one, otherwise from these `Opts`) along with anything else that was
+    /// filtered on. Every other field is defaulted, and
+    /// [`Proxy::satisfies`][crate::proxy::Proxy::satisfies] treats the country, age, and time to
+    /// connect as unknown.
+    ///
+    /// ```
+    /// use lead_oxide::opts::Opts;
+    ///
+    /// let opts = Opts::builder().api_key("<key>".to_string()).lightweight(true).build();
+    /// ```
+    pub fn lightweight(self, lightweight: bool) -> Self {
+        self.format_txt(lightweight)
+    }
+
+    /// Layers `other` on top of this builder, where every field set in `other` replaces the one
+    /// here and unset fields are left as is. This follows the same rules as
+    /// [`Opts::merge`][Opts::merge], so country lists of the same kind are combined while an
+    /// allowlist and a blocklist are never mixed, `other`'s list takes precedence instead.
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::{opts::Opts, types::{Countries, Protocol}};
+    ///
+    /// let base = Opts::builder()
+    ///     .protocol(Protocol::Socks5)
+    ///     .countries(Countries::allow().country(Country::US));
+    /// let merged = base.merge(Opts::builder().countries(Countries::allow().country(Country::CA)));
+    /// assert_eq!(
+    ///     merged,
+    ///     Opts::builder()
+    ///         .protocol(Protocol::Socks5)
+    ///         .countries(Countries::allow().countries(&[Country::US, Country::CA]))
+    /// );
+    /// ```
+    pub fn merge(self, other: OptsBuilder) -> OptsBuilder {
+        let (protocol, protocols) = if other.protocol.is_some() || !other.protocols.is_empty() {
+            (other.protocol, other.protocols)
+        } else {
+            (self.protocol, self.protocols)
+        };
+        // Same goes for the port and port range
+        let (port, port_range) = if other.port.is_some() || other.port_range.is_some() {
+            (other.port, other.port_range)
+        } else {
+            (self.port, self.port_range)
+        };
+        let countries = match (self.countries, other.countries) {
+            (Some(base), Some(overrides)) => Some(base.merge(&overrides)),
+            (base, overrides) => overrides.or(base),
+        };
+        let mut excluded_countries = self.excluded_countries;
+        for country in other.excluded_countries {
+            if !excluded_countries.contains(&country) {
+                excluded_countries.push(country);
+            }
+        }
+
+        Self {
+            api_key: other.api_key.or(self.api_key),
+            level: other.level.or(self.level),
+            protocol,
+            protocols,
+            countries,
+            excluded_countries,
+            last_checked: other.last_checked.or(self.last_checked),
+            port,
+            port_range,
+            time_to_connect: other.time_to_connect.or(self.time_to_connect),
+            cookies: other.cookies.or(self.cookies),
+            connects_to_google: other.connects_to_google.or(self.connects_to_google),
+            https: other.https.or(self.https),
+            post: other.post.or(self.post),
+            referer: other.referer.or(self.referer),
+            forwards_user_agent: other.forwards_user_agent.or(self.forwards_user_agent),
+            keep_unknown_countries: other.keep_unknown_countries.or(self.keep_unknown_countries),
+            max_age: other.max_age.or(self.max_age),
+            max_connect_client_side: other
+                .max_connect_client_side
+                .or(self.max_connect_client_side),
+            min_request_delay: other.min_request_delay.or(self.min_request_delay),
+            request_timeout: other.request_timeout.or(self.request_timeout),
+            user_agent: other.user_agent.or(self.user_agent),
+            api_base: other.api_base.or(self.api_base),
+            dedupe: other.dedupe.or(self.dedupe),
+            independent_rate_limit: other.independent_rate_limit.or(self.independent_rate_limit),
+            format_txt: other.format_txt.or(self.format_txt),
+        }
+    }
+
     /// Constructs the `OptsBuilder` into the corresponding [`Opts`][Opts] value.
+    ///
+    /// # Panics
+    ///
+    /// Panics on any of the combinations of options caught by
+    /// [`try_build`][OptsBuilder::try_build].
     pub fn build(self) -> Opts {
-        Opts::from(self)
+        self.try_build().expect("Invalid combination of options")
+    }
+
+    /// Like [`build`][OptsBuilder::build], but returns an error for combinations of options that
+    /// don't do what they look like they would. Currently this catches:
+    ///
+    /// - An allowlist with no countries which is the same as not filtering on countries at all
+    /// - An [`api_base`][OptsBuilder::api_base] that isn't an http(s) URL
+    /// - A [`port_range`][OptsBuilder::port_range] that's empty, so no proxy could ever match
+    /// - An allowlist where every country is also in
+    ///   [`exclude_countries`][OptsBuilder::exclude_countries], so no proxy could ever match
+    /// - Only allowing HTTP proxies that support POST but not HTTPS, which is contradictory
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::{errors::OptsError, opts::Opts, types::Countries};
+    ///
+    /// let empty = Opts::builder().countries(Countries::allow()).try_build();
+    /// assert_eq!(empty, Err(OptsError::EmptyAllowList));
+    ///
+    /// let opts = Opts::builder()
+    ///     .countries(Countries::allow().country(Country::US))
+    ///     .try_build();
+    /// assert!(opts.is_ok());
+    /// ```
+    pub fn try_build(self) -> Result<Opts, OptsError> {
+        if let Some(Countries::AllowList(list)) = &self.countries {
+            if list.is_empty() {
+                return Err(OptsError::EmptyAllowList);
+            }
+            let excluded = |tag: &str| {
+                self.excluded_countries
+                    .iter()
+                    .any(|country| country.to_string() == tag)
+            };
+            if list.split(',').all(excluded) {
+                return Err(OptsError::ExcludedAllowList);
+            }
+        }
+        if let Some(range) = &self.port_range {
+            if range.is_empty() {
+                return Err(OptsError::EmptyPortRange {
+                    start: *range.start(),
+                    end: *range.end(),
+                });
+            }
+        }
+        if let Some(api_base) = &self.api_base {
+            if !api_base.starts_with("http://") && !api_base.starts_with("https://") {
+                return Err(OptsError::InvalidApiBase {
+                    api_base: api_base.clone(),
+                });
+            }
+        }
+        let only_http = match self.protocols.as_slice() {
+            [] => self.protocol == Some(Protocol::Http),
+            protocols => protocols == [Protocol::Http],
+        };
+        if only_http && self.post == Some(true) && self.https == Some(false) {
+            return Err(OptsError::HttpPostWithoutHttps);
+        }
+
+        Ok(Opts::from(self))
     }
 }
 
+/// Identifies one of the filters that can be set on [`Opts`][Opts].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Filter {
+    Level,
+    Protocol,
+    Countries,
+    LastChecked,
+    Port,
+    TimeToConnect,
+    Cookies,
+    ConnectsToGoogle,
+    Https,
+    Post,
+    Referer,
+    ForwardsUserAgent,
+}
+
+impl Filter {
+    /// Every filter in the order they're declared.
+    pub const ALL: [Filter; 12] = [
+        Filter::Level,
+        Filter::Protocol,
+        Filter::Countries,
+        Filter::LastChecked,
+        Filter::Port,
+        Filter::TimeToConnect,
+        Filter::Cookies,
+        Filter::ConnectsToGoogle,
+        Filter::Https,
+        Filter::Post,
+        Filter::Referer,
+        Filter::ForwardsUserAgent,
+    ];
+}
+
 /// Internal
-#[derive(Serialize_repr, Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Serialize_repr, Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[repr(u8)]
 pub(crate) enum Limit {
+    #[default]
     Free = 5,
     Premium = 20,
 }
 
-impl Default for Limit {
-    fn default() -> Self {
-        Self::Free
-    }
-}
-
 /// Internal
-#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
-enum Format {
-    // Techically txt is also allowed, but this library only uses json
+pub(crate) enum Format {
+    #[default]
     Json,
-}
-
-impl Default for Format {
-    fn default() -> Self {
-        Self::Json
-    }
+    Txt,
 }
 
 /// A set of options to constrain the returned proxies.
@@ -186,16 +714,27 @@ impl Default for Format {
 pub struct Opts {
     #[serde(rename = "api")]
     api_key: Option<String>,
+    // Transparent can't be sent to the API so it's only filtered locally
+    #[serde(skip_serializing_if = "is_transparent")]
     level: Option<Level>,
     #[serde(rename = "type")]
-    protocol: Option<Protocol>,
+    pub(crate) protocol: Option<Protocol>,
+    // Multiple protocols get requested one at a time through `protocol`
+    #[serde(skip)]
+    protocols: Vec<Protocol>,
     // An empty country list is essentially `None`
     #[serde(flatten, skip_serializing_if = "Countries::is_empty")]
     countries: Countries,
+    // Filtered locally so that it can be combined with an allowlist
+    #[serde(skip)]
+    excluded_countries: Vec<Country>,
     #[serde(rename = "last_check")]
     last_checked: Option<u64>,
     // Note: using a port of 0 will return any port from the api :silly:
     port: Option<NonZeroU16>,
+    // Filtered locally since the API only takes a single port
+    #[serde(skip)]
+    port_range: Option<RangeInclusive<u16>>,
     #[serde(rename = "speed")]
     time_to_connect: Option<u64>,
     cookies: Option<bool>,
@@ -207,7 +746,31 @@ pub struct Opts {
     #[serde(rename = "user_agent")]
     forwards_user_agent: Option<bool>,
     pub(crate) limit: Limit,
-    format: Format,
+    pub(crate) format: Format,
+    #[serde(skip)]
+    pub(crate) keep_unknown_countries: bool,
+    #[serde(skip)]
+    max_age: Option<Duration>,
+    #[serde(skip)]
+    max_connect_client_side: Option<Duration>,
+    // Whether the API's filters above were filled in from the client side ones instead of being set
+    // explicitly, so merging can fill them in again
+    #[serde(skip)]
+    last_checked_derived: bool,
+    #[serde(skip)]
+    time_to_connect_derived: bool,
+    #[serde(skip)]
+    min_request_delay: Option<Duration>,
+    #[serde(skip)]
+    pub(crate) request_timeout: Option<Duration>,
+    #[serde(skip)]
+    user_agent: Option<String>,
+    #[serde(skip)]
+    api_base: Option<String>,
+    #[serde(skip)]
+    pub(crate) dedupe: bool,
+    #[serde(skip)]
+    pub(crate) independent_rate_limit: bool,
 }
 
 impl Opts {
@@ -216,14 +779,397 @@ impl Opts {
         OptsBuilder::default()
     }
 
+    /// The maximum yield configuration for when you just want proxies and don't care about any of
+    /// their attributes. This is the same as `Opts::default()` except that proxies with an
+    /// unknown country are kept instead of dropped (see
+    /// [`OptsBuilder::keep_unknown_countries`][OptsBuilder::keep_unknown_countries]), which makes
+    /// around 10% more proxies available per request.
+    ///
+    /// ```
+    /// use lead_oxide::{fetcher::Fetcher, opts::Opts};
+    ///
+    /// let fetcher = Fetcher::new(Opts::any());
+    /// ```
+    pub fn any() -> Self {
+        Self::builder().keep_unknown_countries(true).build()
+    }
+
+    /// The most proxies that a single request to the API can return, which is 5 without an API
+    /// key and 20 with one. Handy for sizing calls to
+    /// [`Fetcher::try_get`][crate::fetcher::Fetcher::try_get] so that no request goes to waste.
+    ///
+    /// ```
+    /// use lead_oxide::opts::Opts;
+    ///
+    /// assert_eq!(Opts::default().per_request_limit(), 5);
+    /// assert_eq!(
+    ///     Opts::builder()
+    ///         .api_key("<key>".to_string())
+    ///         .build()
+    ///         .per_request_limit(),
+    ///     20
+    /// );
+    /// ```
+    pub fn per_request_limit(&self) -> usize {
+        self.limit as usize
+    }
+
+    /// Encodes the `Opts` as the query parameters sent to the API. This is handy for logging or
+    /// bug reports since no request is made. When filtering on multiple protocols each request
+    /// also sets `type` to the protocol it's currently requesting, which isn't included here.
+    ///
+    /// ```
+    /// use lead_oxide::{opts::Opts, types::Protocol};
+    ///
+    /// let opts = Opts::builder().protocol(Protocol::Socks5).post(true).build();
+    /// assert_eq!(
+    ///     opts.to_query_string().unwrap(),
+    ///     "type=socks5&post=true&limit=5&format=json"
+    /// );
+    /// ```
+    pub fn to_query_string(&self) -> Result<String, serde_urlencoded::ser::Error> {
+        serde_urlencoded::to_string(self)
+    }
+
+    /// The inverse of [`to_query_string`][Opts::to_query_string] for reproducing a logged
+    /// request. This accepts either the query on its own or the full URL. The limit is set from
+    /// whether there's an API key like usual, and anything not in the query like local filters is
+    /// left at its default.
+    ///
+    /// ```
+    /// use lead_oxide::{opts::Opts, types::Protocol};
+    ///
+    /// let opts = Opts::from_query_string(
+    ///     "http://pubproxy.com/api/proxy?type=socks5&post=true&limit=5&format=json",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(opts, Opts::builder().protocol(Protocol::Socks5).post(true).build());
+    /// ```
+    pub fn from_query_string(query: &str) -> Result<Self, serde_urlencoded::de::Error> {
+        let query = query.split_once('?').map_or(query, |(_, query)| query);
+        let params: QueryParams = serde_urlencoded::from_str(query)?;
+        params.into_builder().map(OptsBuilder::build)
+    }
+
+    /// Returns `true` when any filter is set to constrain the returned proxies. The API key along
+    /// with the internally managed values don't count as filters.
+    ///
+    /// ```
+    /// use lead_oxide::{opts::Opts, types::Level};
+    ///
+    /// assert!(!Opts::default().is_filtered());
+    /// assert!(Opts::builder().level(Level::Elite).build().is_filtered());
+    /// ```
+    pub fn is_filtered(&self) -> bool {
+        self.level.is_some()
+            || self.protocol.is_some()
+            || !self.protocols.is_empty()
+            || !self.countries.is_empty()
+            || !self.excluded_countries.is_empty()
+            || self.last_checked.is_some()
+            || self.port.is_some()
+            || self.port_range.is_some()
+            || self.time_to_connect.is_some()
+            || self.cookies.is_some()
+            || self.connects_to_google.is_some()
+            || self.https.is_some()
+            || self.post.is_some()
+            || self.referer.is_some()
+            || self.forwards_user_agent.is_some()
+            || self.max_age.is_some()
+            || self.max_connect_client_side.is_some()
+    }
+
+    /// Layers `overrides` on top of these `Opts`, where every filter set in `overrides` replaces
+    /// the one here. Country lists of the same kind are combined, so allowing `US` over a base
+    /// allowing `CA` allows both, while an allowlist overriding a blocklist (or vice versa)
+    /// replaces it entirely. Excluded countries from both sides are excluded. Proxies with unknown
+    /// countries are kept if either side keeps them, and likewise for deduping, independent rate
+    /// limits, and the `txt` format (see [`lightweight`][OptsBuilder::lightweight]), so an override
+    /// can't switch a `txt` base back to json.
+    ///
+    /// The API key works like any other field, but note that the limit is recomputed from the
+    /// merged key, so an override with a key will use the premium limit.
+    ///
+    /// ```
+    /// use lead_oxide::{opts::Opts, types::{Level, Protocol}};
+    ///
+    /// let base = Opts::builder().level(Level::Elite).https(true).build();
+    /// let merged = base.merge(&Opts::builder().protocol(Protocol::Socks5).https(false).build());
+    /// assert_eq!(
+    ///     merged,
+    ///     Opts::builder()
+    ///         .level(Level::Elite)
+    ///         .protocol(Protocol::Socks5)
+    ///         .https(false)
+    ///         .build()
+    /// );
+    /// ```
+    pub fn merge(&self, overrides: &Opts) -> Opts {
+        Opts::from(self.to_builder().merge(overrides.to_builder()))
+    }
+
+    /// Internal
+    // The builder that builds these `Opts`, so that merging only has one set of rules. Flags that
+    // are off are left unset so that they can't turn off the other side's when merging
+    fn to_builder(&self) -> OptsBuilder {
+        let flag = |flag: bool| if flag { Some(true) } else { None };
+
+        OptsBuilder {
+            api_key: self.api_key.clone(),
+            level: self.level,
+            protocol: self.protocol,
+            protocols: self.protocols.clone(),
+            countries: Some(self.countries.clone()),
+            excluded_countries: self.excluded_countries.clone(),
+            last_checked: self
+                .last_checked
+                .filter(|_| !self.last_checked_derived)
+                .map(|mins| LastChecked::new_unchecked(Duration::from_secs(mins * 60))),
+            port: self.port,
+            port_range: self.port_range.clone(),
+            time_to_connect: self
+                .time_to_connect
+                .filter(|_| !self.time_to_connect_derived)
+                .map(|secs| TimeToConnect::new_unchecked(Duration::from_secs(secs))),
+            cookies: self.cookies,
+            connects_to_google: self.connects_to_google,
+            https: self.https,
+            post: self.post,
+            referer: self.referer,
+            forwards_user_agent: self.forwards_user_agent,
+            keep_unknown_countries: flag(self.keep_unknown_countries),
+            max_age: self.max_age,
+            max_connect_client_side: self.max_connect_client_side,
+            min_request_delay: self.min_request_delay,
+            request_timeout: self.request_timeout,
+            user_agent: self.user_agent.clone(),
+            api_base: self.api_base.clone(),
+            dedupe: flag(self.dedupe),
+            independent_rate_limit: flag(self.independent_rate_limit),
+            format_txt: flag(self.format == Format::Txt),
+        }
+    }
+
+    /// Internal
+    pub(crate) fn allows(&self, proxy: &Proxy, now: NaiveDateTime) -> bool {
+        if self.level == Some(Level::Transparent) && proxy.level != Level::Transparent {
+            return false;
+        }
+        if self.excluded_countries.contains(&proxy.country) {
+            return false;
+        }
+        if let Some(range) = &self.port_range {
+            if !range.contains(&proxy.socket.port()) {
+                return false;
+            }
+        }
+        if let Some(max_connect) = self.max_connect_client_side {
+            if proxy.time_to_connect > max_connect {
+                return false;
+            }
+        }
+
+        match self.max_age {
+            Some(max_age) => !proxy.is_stale(max_age, now),
+            None => true,
+        }
+    }
+
+    /// Internal
+    // `None` when the filter isn't set, otherwise if `proxy` matches it
+    pub(crate) fn check(&self, filter: Filter, proxy: &Proxy, now: NaiveDateTime) -> Option<bool> {
+        // The `txt` format leaves these out, so they're defaulted and there's nothing to check
+        let unknown = matches!(
+            filter,
+            Filter::Countries | Filter::LastChecked | Filter::TimeToConnect
+        );
+        if self.format == Format::Txt && unknown {
+            return None;
+        }
+
+        let supports = &proxy.supports;
+        let matches = |field: Option<bool>, supported: bool| field.map(|value| value == supported);
+
+        match filter {
+            Filter::Level => self.level.map(|level| proxy.level == level),
+            Filter::Protocol => {
+                if self.protocols.is_empty() {
+                    self.protocol.map(|protocol| proxy.protocol == protocol)
+                } else {
+                    Some(self.protocols.contains(&proxy.protocol))
+                }
+            }
+            Filter::Countries => {
+                if self.countries.is_empty() {
+                    None
+                } else {
+                    let country = proxy.country.to_string();
+                    let listed = |list: &str| list.split(',').any(|tag| tag == country);
+                    Some(match &self.countries {
+                        Countries::AllowList(list) => listed(list),
+                        Countries::BlockList(list) => !listed(list),
+                    })
+                }
+            }
+            Filter::LastChecked => {
+                self.last_checked
+                    .map(|minutes| match (now - proxy.last_checked).to_std() {
+                        Ok(age) => age <= Duration::from_secs(minutes * 60),
+                        Err(_) => true,
+                    })
+            }
+            Filter::Port => match &self.port_range {
+                Some(range) => Some(range.contains(&proxy.socket.port())),
+                None => self.port.map(|port| proxy.socket.port() == port.get()),
+            },
+            Filter::TimeToConnect => self
+                .time_to_connect
+                .map(|secs| proxy.time_to_connect <= Duration::from_secs(secs)),
+            Filter::Cookies => matches(self.cookies, supports.cookies),
+            Filter::ConnectsToGoogle => {
+                matches(self.connects_to_google, supports.connects_to_google)
+            }
+            Filter::Https => matches(self.https, supports.https),
+            Filter::Post => matches(self.post, supports.post),
+            Filter::Referer => matches(self.referer, supports.referer),
+            Filter::ForwardsUserAgent => {
+                matches(self.forwards_user_agent, supports.forwards_user_agent)
+            }
+        }
+    }
+
+    /// Internal
+    pub(crate) fn relax(&mut self, filter: Filter) -> bool {
+        fn clear<T>(field: &mut Option<T>) -> bool {
+            field.take().is_some()
+        }
+
+        match filter {
+            Filter::Level => clear(&mut self.level),
+            Filter::Protocol => {
+                let had_protocols = !mem::take(&mut self.protocols).is_empty();
+                clear(&mut self.protocol) || had_protocols
+            }
+            Filter::Countries => {
+                let was_set = !self.countries.is_empty();
+                self.countries = Countries::default();
+                was_set
+            }
+            Filter::LastChecked => clear(&mut self.last_checked),
+            Filter::Port => {
+                let had_range = clear(&mut self.port_range);
+                clear(&mut self.port) || had_range
+            }
+            Filter::TimeToConnect => clear(&mut self.time_to_connect),
+            Filter::Cookies => clear(&mut self.cookies),
+            Filter::ConnectsToGoogle => clear(&mut self.connects_to_google),
+            Filter::Https => clear(&mut self.https),
+            Filter::Post => clear(&mut self.post),
+            Filter::Referer => clear(&mut self.referer),
+            Filter::ForwardsUserAgent => clear(&mut self.forwards_user_agent),
+        }
+    }
+
+    /// Internal
+    pub(crate) fn request_delay(&self) -> Duration {
+        self.min_request_delay.unwrap_or(constants::DELAY)
+    }
+
+    /// Internal
+    pub(crate) fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(constants::USER_AGENT)
+    }
+
+    /// Internal
+    pub(crate) fn api_base(&self) -> &str {
+        self.api_base.as_deref().unwrap_or(constants::API_URI)
+    }
+
     /// Internal
     pub(crate) fn is_premium(&self) -> bool {
         self.api_key.is_some()
     }
+
+    /// Internal
+    // The `txt` format only has sockets, but anything the API filtered on is still known
+    pub(crate) fn fill_known(&self, proxy: &mut Proxy) {
+        let supports = &mut proxy.supports;
+        let fields = [
+            (self.cookies, &mut supports.cookies),
+            (self.connects_to_google, &mut supports.connects_to_google),
+            (self.https, &mut supports.https),
+            (self.post, &mut supports.post),
+            (self.referer, &mut supports.referer),
+            (self.forwards_user_agent, &mut supports.forwards_user_agent),
+        ];
+        for (field, supported) in fields {
+            if let Some(value) = field {
+                *supported = value;
+            }
+        }
+
+        if let Some(level) = self.level {
+            proxy.level = level;
+        }
+        if let Some(protocol) = self.protocol {
+            proxy.protocol = protocol;
+        }
+    }
+
+    /// Internal
+    // Picks the protocol for the next request when there are several, advancing `turn`
+    pub(crate) fn next_request(&self, turn: &mut usize) -> Self {
+        if self.protocols.is_empty() {
+            return self.clone();
+        }
+
+        let protocol = self.protocols[*turn % self.protocols.len()];
+        *turn = (*turn + 1) % self.protocols.len();
+        Self {
+            protocol: Some(protocol),
+            protocols: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Internal
+    pub(crate) fn with_format(&self, format: Format) -> Self {
+        Self {
+            format,
+            ..self.clone()
+        }
+    }
+
+    /// Internal
+    pub(crate) fn with_api_key(&self, api_key: String) -> Self {
+        Self {
+            api_key: Some(api_key),
+            limit: Limit::Premium,
+            ..self.clone()
+        }
+    }
 }
 
 impl From<OptsBuilder> for Opts {
     fn from(builder: OptsBuilder) -> Self {
+        let max_age = builder.max_age;
+        let max_connect = builder.max_connect_client_side;
+        let derived_last_checked = max_age.and_then(max_age_in_api_range);
+        let derived_time_to_connect = max_connect.and_then(max_connect_in_api_range);
+        // A range of a single port is the same as setting just that port
+        let (port, port_range) = match builder.port_range {
+            Some(range) if range.start() == range.end() && *range.start() != 0 => {
+                (NonZeroU16::new(*range.start()), None)
+            }
+            port_range => (builder.port, port_range),
+        };
+        // A single protocol in the list is the same as setting just that protocol
+        let (protocol, protocols) = match builder.protocols.as_slice() {
+            [protocol] => (Some(*protocol), Vec::new()),
+            _ => (builder.protocol, builder.protocols),
+        };
+
         Self {
             limit: match builder.api_key {
                 Some(_) => Limit::Premium,
@@ -231,23 +1177,294 @@ impl From<OptsBuilder> for Opts {
             },
             api_key: builder.api_key,
             level: builder.level,
-            protocol: builder.protocol,
+            protocol,
+            protocols,
             countries: builder.countries.unwrap_or_default(),
+            excluded_countries: builder.excluded_countries,
             last_checked: builder
                 .last_checked
-                .map(|last_checked| last_checked.value().as_secs() / 60),
-            port: builder.port,
+                .as_ref()
+                .map(|last_checked| last_checked.value().as_secs() / 60)
+                .or(derived_last_checked),
+            port,
+            port_range,
             time_to_connect: builder
                 .time_to_connect
-                .map(|time_to_connect| time_to_connect.value().as_secs()),
+                .as_ref()
+                .map(|time_to_connect| time_to_connect.value().as_secs())
+                .or(derived_time_to_connect),
             cookies: builder.cookies,
             connects_to_google: builder.connects_to_google,
             https: builder.https,
             post: builder.post,
             referer: builder.referer,
             forwards_user_agent: builder.forwards_user_agent,
-            format: Format::default(),
+            format: if builder.format_txt.unwrap_or_default() {
+                Format::Txt
+            } else {
+                Format::Json
+            },
+            keep_unknown_countries: builder.keep_unknown_countries.unwrap_or_default(),
+            max_age,
+            max_connect_client_side: max_connect,
+            last_checked_derived: builder.last_checked.is_none() && derived_last_checked.is_some(),
+            time_to_connect_derived: builder.time_to_connect.is_none()
+                && derived_time_to_connect.is_some(),
+            min_request_delay: builder.min_request_delay,
+            request_timeout: builder.request_timeout,
+            user_agent: builder.user_agent,
+            api_base: builder.api_base.map(normalize_api_base),
+            dedupe: builder.dedupe.unwrap_or_default(),
+            independent_rate_limit: builder.independent_rate_limit.unwrap_or_default(),
+        }
+    }
+}
+
+/// Internal
+// Config file representations for `OptsBuilder` which are separate from the API's query params
+mod config {
+    use std::{convert::TryFrom, time::Duration};
+
+    use crate::types::{Countries, LastChecked, TimeToConnect};
+
+    use iso_country::Country;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum CountriesConfig {
+        Allow(Vec<Country>),
+        Block(Vec<Country>),
+    }
+
+    fn serialize_secs<S: Serializer>(
+        duration: Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration
+            .map(|duration| duration.as_secs_f64())
+            .serialize(serializer)
+    }
+
+    fn deserialize_secs<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<f64>::deserialize(deserializer)?
+            .map(|secs| Duration::try_from_secs_f64(secs).map_err(de::Error::custom))
+            .transpose()
+    }
+
+    pub(super) mod secs {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serialize_secs(*duration, serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            deserialize_secs(deserializer)
+        }
+    }
+
+    pub(super) mod last_checked {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            last_checked: &Option<LastChecked>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serialize_secs(last_checked.as_ref().map(LastChecked::value), serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<LastChecked>, D::Error> {
+            deserialize_secs(deserializer)?
+                .map(|duration| LastChecked::try_from(duration).map_err(de::Error::custom))
+                .transpose()
+        }
+    }
+
+    pub(super) mod time_to_connect {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            time_to_connect: &Option<TimeToConnect>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serialize_secs(
+                time_to_connect.as_ref().map(TimeToConnect::value),
+                serializer,
+            )
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<TimeToConnect>, D::Error> {
+            deserialize_secs(deserializer)?
+                .map(|duration| TimeToConnect::try_from(duration).map_err(de::Error::custom))
+                .transpose()
+        }
+    }
+
+    pub(super) mod countries {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            countries: &Option<Countries>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            countries
+                .as_ref()
+                .map(|countries| match countries {
+                    Countries::AllowList(_) => CountriesConfig::Allow(countries.iter().collect()),
+                    Countries::BlockList(_) => CountriesConfig::Block(countries.iter().collect()),
+                })
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Countries>, D::Error> {
+            Option::<CountriesConfig>::deserialize(deserializer)?
+                .map(|config| {
+                    match config {
+                        CountriesConfig::Allow(list) => Countries::allow().try_countries(&list),
+                        CountriesConfig::Block(list) => Countries::block().try_countries(&list),
+                    }
+                    .map_err(de::Error::custom)
+                })
+                .transpose()
+        }
+    }
+}
+
+/// Internal
+// The query params get appended directly so make sure there's somewhere to put them
+fn normalize_api_base(mut api_base: String) -> String {
+    if !api_base.ends_with('?') && !api_base.ends_with('&') {
+        api_base.push(if api_base.contains('?') { '&' } else { '?' });
+    }
+
+    api_base
+}
+
+/// Internal
+// The API's query parameters, see `Opts::from_query_string`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryParams {
+    api: Option<String>,
+    level: Option<Level>,
+    #[serde(rename = "type")]
+    protocol: Option<Protocol>,
+    country: Option<String>,
+    not_country: Option<String>,
+    last_check: Option<u64>,
+    port: Option<NonZeroU16>,
+    speed: Option<u64>,
+    cookies: Option<bool>,
+    google: Option<bool>,
+    https: Option<bool>,
+    post: Option<bool>,
+    referer: Option<bool>,
+    user_agent: Option<bool>,
+    // Always determined by the key
+    #[allow(dead_code)]
+    limit: Option<u8>,
+    format: Option<String>,
+}
+
+impl QueryParams {
+    fn into_builder(self) -> Result<OptsBuilder, serde_urlencoded::de::Error> {
+        type Error = serde_urlencoded::de::Error;
+
+        let countries = |list: &str, countries: Countries| {
+            let list = list
+                .split(',')
+                .filter(|code| !code.is_empty())
+                .map(|code| {
+                    code.parse::<Country>()
+                        .map_err(|_| Error::custom(format!("invalid country: '{}'", code)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            countries
+                .try_countries(&list)
+                .map_err(|err| Error::custom(err.to_string()))
+        };
+
+        let mut builder = OptsBuilder {
+            api_key: self.api,
+            level: self.level,
+            protocol: self.protocol,
+            port: self.port,
+            cookies: self.cookies,
+            connects_to_google: self.google,
+            https: self.https,
+            post: self.post,
+            referer: self.referer,
+            forwards_user_agent: self.user_agent,
+            ..OptsBuilder::default()
+        };
+        builder.countries = match (&self.country, &self.not_country) {
+            (Some(_), Some(_)) => {
+                return Err(Error::custom(
+                    "`country` and `not_country` can't be used together",
+                ))
+            }
+            (Some(list), None) => Some(countries(list, Countries::allow())?),
+            (None, Some(list)) => Some(countries(list, Countries::block())?),
+            (None, None) => None,
+        };
+        if let Some(mins) = self.last_check {
+            let last_checked = LastChecked::try_from(Duration::from_secs(mins * 60))
+                .map_err(|err| Error::custom(err.to_string()))?;
+            builder.last_checked = Some(last_checked);
+        }
+        if let Some(secs) = self.speed {
+            let time_to_connect = TimeToConnect::try_from(Duration::from_secs(secs))
+                .map_err(|err| Error::custom(err.to_string()))?;
+            builder.time_to_connect = Some(time_to_connect);
         }
+        builder.format_txt = match self.format.as_deref() {
+            None | Some("json") => None,
+            Some("txt") => Some(true),
+            Some(format) => return Err(Error::custom(format!("invalid format: '{}'", format))),
+        };
+
+        Ok(builder)
+    }
+}
+
+/// Internal
+fn is_transparent(level: &Option<Level>) -> bool {
+    *level == Some(Level::Transparent)
+}
+
+// Rounds up to the API's resolution of minutes so that the API never filters more than we do
+fn max_age_in_api_range(max_age: Duration) -> Option<u64> {
+    let minutes = (max_age.as_secs() + u64::from(max_age.subsec_nanos() > 0)).div_ceil(60);
+    let (min, max) = LastChecked::BOUNDS;
+    if (min.as_secs() / 60..=max.as_secs() / 60).contains(&minutes) {
+        Some(minutes)
+    } else {
+        None
+    }
+}
+
+// Rounds up to the API's resolution of seconds so that the API never filters more than we do
+fn max_connect_in_api_range(max_connect: Duration) -> Option<u64> {
+    let secs = max_connect.as_secs() + u64::from(max_connect.subsec_nanos() > 0);
+    let (min, max) = TimeToConnect::BOUNDS;
+    if (min.as_secs()..=max.as_secs()).contains(&secs) {
+        Some(secs)
+    } else {
+        None
     }
 }
 
@@ -255,15 +1472,668 @@ impl From<OptsBuilder> for Opts {
 mod tests {
     use super::*;
 
-    use std::{convert::TryFrom, time::Duration};
+    use crate::proxy::{proxies_from_txt, test_util::proxy};
+
+    use std::convert::TryFrom;
 
     use iso_country::Country;
 
+    #[test]
+    fn is_filtered() {
+        // Neither the default or just setting a key filter anything
+        assert!(!Opts::default().is_filtered());
+        assert!(!Opts::builder()
+            .api_key("<key>".to_string())
+            .build()
+            .is_filtered());
+        // and neither does an empty country list (which `build` rejects for allowlists)
+        assert!(!Opts::from(Opts::builder().countries(Countries::allow())).is_filtered());
+        assert!(!Opts::builder()
+            .countries(Countries::block())
+            .build()
+            .is_filtered());
+
+        assert!(Opts::builder()
+            .countries(Countries::block().country(Country::CH))
+            .build()
+            .is_filtered());
+        assert!(Opts::builder().post(false).build().is_filtered());
+        assert!(Opts::builder()
+            .port(NonZeroU16::new(8080).unwrap())
+            .build()
+            .is_filtered());
+    }
+
+    #[test]
+    fn any() {
+        let any = Opts::any();
+        assert!(!any.is_filtered());
+        assert!(any.keep_unknown_countries);
+        assert_eq!(
+            any,
+            Opts {
+                keep_unknown_countries: true,
+                ..Opts::default()
+            }
+        );
+    }
+
+    #[test]
+    fn builder_merge() {
+        let base = Opts::builder()
+            .level(Level::Elite)
+            .protocol(Protocol::Http)
+            .countries(Countries::allow().country(Country::US))
+            .https(true);
+
+        // Unset fields pass through
+        assert_eq!(base.clone().merge(Opts::builder()), base);
+        assert_eq!(Opts::builder().merge(base.clone()), base);
+
+        // Set fields override, including back to `false`
+        let merged = base.clone().merge(
+            Opts::builder()
+                .protocols(&[Protocol::Socks4, Protocol::Socks5])
+                .https(false)
+                .dedupe(true),
+        );
+        assert_eq!(
+            merged,
+            Opts::builder()
+                .level(Level::Elite)
+                .protocols(&[Protocol::Socks4, Protocol::Socks5])
+                .countries(Countries::allow().country(Country::US))
+                .https(false)
+                .dedupe(true)
+        );
+
+        // Lists of the same kind are unioned while a different kind takes precedence
+        let allow = base
+            .clone()
+            .merge(
+                Opts::builder()
+                    .countries(Countries::allow().countries(&[Country::US, Country::CA])),
+            )
+            .build();
+        assert_eq!(
+            allow.countries,
+            Countries::allow().countries(&[Country::US, Country::CA])
+        );
+        let block = base
+            .merge(Opts::builder().countries(Countries::block().country(Country::CN)))
+            .build();
+        assert_eq!(block.countries, Countries::block().country(Country::CN));
+    }
+
+    #[test]
+    fn config() -> Result<(), serde_json::Error> {
+        let builder = Opts::builder()
+            .api_key("<key>".to_string())
+            .level(Level::Transparent)
+            .protocols(&[Protocol::Socks4, Protocol::Socks5])
+            .countries(Countries::block().countries(&[Country::CN, Country::RU]))
+            .last_checked(LastChecked::try_from(Duration::from_secs(10 * 60)).unwrap())
+            .time_to_connect(TimeToConnect::try_from(Duration::from_secs(5)).unwrap())
+            .port(NonZeroU16::new(1080).unwrap())
+            .https(true)
+            .post(false)
+            .max_age(Duration::from_secs(90))
+            .min_request_delay(Duration::from_millis(1_500))
+            .request_timeout(Duration::from_secs(10))
+            .user_agent("my-scraper/1.0".to_string())
+            .dedupe(true);
+
+        let json = serde_json::to_value(&builder)?;
+        assert_eq!(json["api_key"], "<key>");
+        assert_eq!(json["level"], "transparent");
+        assert_eq!(json["protocols"], serde_json::json!(["socks4", "socks5"]));
+        assert_eq!(
+            json["countries"],
+            serde_json::json!({ "block": ["CN", "RU"] })
+        );
+        assert_eq!(json["last_checked_secs"], 600.0);
+        assert_eq!(json["min_request_delay_secs"], 1.5);
+        assert_eq!(json.get("cookies"), None);
+
+        let round_tripped: OptsBuilder = serde_json::from_value(json)?;
+        assert_eq!(round_tripped, builder);
+        assert_eq!(serde_json::from_str::<OptsBuilder>("{}")?, Opts::builder());
+
+        // Invalid values and typos are rejected instead of being silently ignored
+        assert!(serde_json::from_str::<OptsBuilder>(r#"{"last_checked_secs": 1}"#).is_err());
+        assert!(serde_json::from_str::<OptsBuilder>(r#"{"max_age_secs": -1}"#).is_err());
+        assert!(serde_json::from_str::<OptsBuilder>(r#"{"htps": true}"#).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn api_base() {
+        let api_base = |base: &str| Opts::builder().api_base(base.to_string()).build();
+
+        assert_eq!(Opts::default().api_base(), constants::API_URI);
+        assert_eq!(
+            api_base("http://localhost/api").api_base(),
+            "http://localhost/api?"
+        );
+        assert_eq!(
+            api_base("http://localhost/api?").api_base(),
+            "http://localhost/api?"
+        );
+        assert_eq!(
+            api_base("http://localhost/api?token=1").api_base(),
+            "http://localhost/api?token=1&"
+        );
+    }
+
+    #[test]
+    fn try_build() {
+        assert_eq!(
+            Opts::builder()
+                .post(true)
+                .countries(Countries::allow())
+                .try_build(),
+            Err(OptsError::EmptyAllowList)
+        );
+        // An empty blocklist is the default so it's fine
+        assert_eq!(
+            Opts::builder().countries(Countries::block()).try_build(),
+            Ok(Opts::default())
+        );
+
+        assert_eq!(
+            Opts::builder()
+                .api_base("localhost:8080".to_string())
+                .try_build(),
+            Err(OptsError::InvalidApiBase {
+                api_base: "localhost:8080".to_string()
+            })
+        );
+
+        let excluded = Opts::builder()
+            .countries(Countries::allow().countries(&[Country::CA, Country::US]))
+            .exclude_countries(&[Country::US, Country::CA]);
+        assert_eq!(excluded.try_build(), Err(OptsError::ExcludedAllowList));
+        // Excluding only part of the allowlist still leaves something to match
+        assert!(Opts::builder()
+            .countries(Countries::allow().countries(&[Country::CA, Country::US]))
+            .exclude_countries(&[Country::US])
+            .try_build()
+            .is_ok());
+
+        let http_post = Opts::builder().protocol(Protocol::Http).post(true);
+        assert_eq!(
+            http_post.clone().https(false).try_build(),
+            Err(OptsError::HttpPostWithoutHttps)
+        );
+        assert_eq!(
+            http_post
+                .clone()
+                .protocols(&[Protocol::Http])
+                .https(false)
+                .try_build(),
+            Err(OptsError::HttpPostWithoutHttps)
+        );
+        // Any other protocol or leaving `https` unset is fine
+        assert!(http_post.clone().try_build().is_ok());
+        assert!(http_post
+            .clone()
+            .protocols(&[Protocol::Http, Protocol::Socks5])
+            .https(false)
+            .try_build()
+            .is_ok());
+
+        let builder = Opts::builder()
+            .countries(Countries::allow().country(Country::US))
+            .api_base("https://localhost:8080".to_string())
+            .post(true);
+        assert_eq!(builder.clone().try_build(), Ok(builder.build()));
+
+        // `build` panics on anything `try_build` catches
+        assert!(std::panic::catch_unwind(|| http_post.https(false).build()).is_err());
+    }
+
+    #[test]
+    fn multiple_protocols() {
+        let socks = Opts::builder()
+            .protocols(&[Protocol::Socks4, Protocol::Socks5, Protocol::Socks4])
+            .build();
+        assert!(socks.is_filtered());
+        assert_eq!(socks.protocol, None);
+        assert_eq!(socks.protocols, [Protocol::Socks4, Protocol::Socks5]);
+
+        // Requests take turns between the protocols
+        let mut turn = 0;
+        let protocols: Vec<_> = (0..3)
+            .map(|_| socks.next_request(&mut turn).protocol)
+            .collect();
+        assert_eq!(
+            protocols,
+            [
+                Some(Protocol::Socks4),
+                Some(Protocol::Socks5),
+                Some(Protocol::Socks4)
+            ]
+        );
+
+        // A single protocol is just a normal protocol filter
+        let http = Opts::builder().protocols(&[Protocol::Http]).build();
+        assert_eq!(http, Opts::builder().protocol(Protocol::Http).build());
+        assert_eq!(http.next_request(&mut turn), http);
+
+        // Merging replaces the protocols like any other filter
+        assert_eq!(socks.merge(&http), http);
+        assert_eq!(http.merge(&socks), socks);
+        assert_eq!(socks.merge(&Opts::default()), socks);
+
+        let mut relaxed = socks.clone();
+        assert!(relaxed.relax(Filter::Protocol));
+        assert!(!relaxed.is_filtered());
+    }
+
+    #[test]
+    fn resetters() {
+        let base = Opts::builder()
+            .level(Level::Elite)
+            .protocol(Protocol::Socks5)
+            .countries(Countries::allow().country(Country::US))
+            .port(NonZeroU16::new(8080).unwrap())
+            .https(true);
+
+        let relaxed = base.clone().any_level();
+        assert_eq!(relaxed.level, None);
+        assert_eq!(relaxed.protocol, Some(Protocol::Socks5));
+
+        let relaxed = base.clone().any_protocol();
+        assert_eq!(relaxed.protocol, None);
+        let relaxed = base.clone().protocols(&[Protocol::Http, Protocol::Socks4]);
+        assert!(relaxed.any_protocol().protocols.is_empty());
+
+        let relaxed = base.clone().any_countries();
+        assert_eq!(relaxed.countries, None);
+
+        let relaxed = base.clone().any_port();
+        assert_eq!(relaxed.port, None);
+        let relaxed = base.clone().port_range(8000..=9000).any_port();
+        assert_eq!(relaxed.port_range, None);
+
+        // Resetting everything leaves just the other filters
+        let relaxed = base.any_level().any_protocol().any_countries().any_port();
+        assert_eq!(relaxed, Opts::builder().https(true));
+
+        // Resetting something that was never set does nothing
+        assert_eq!(Opts::builder().any_level().any_port(), Opts::builder());
+    }
+
+    #[test]
+    fn port_u16() {
+        let builder = Opts::builder().port_u16(1080).unwrap();
+        assert_eq!(builder.port, NonZeroU16::new(1080));
+
+        assert_eq!(
+            Opts::builder().port_u16(0).unwrap_err(),
+            ParamError::out_of_bounds(0, (1, u16::MAX))
+        );
+    }
+
+    #[test]
+    fn port_range() {
+        let now = chrono::Utc::now().naive_utc();
+        let proxy_with_port = |port| proxy().with_port(port);
+
+        // The range is only filtered locally
+        let opts = Opts::builder().port_range(8000..=9000).build();
+        assert!(opts.is_filtered());
+        assert_eq!(opts.to_query_string().unwrap(), "limit=5&format=json");
+        for &(port, allowed) in &[(7999, false), (8000, true), (8080, true), (9001, false)] {
+            assert_eq!(opts.allows(&proxy_with_port(port), now), allowed);
+            assert_eq!(
+                opts.check(Filter::Port, &proxy_with_port(port), now),
+                Some(allowed)
+            );
+        }
+
+        // A single port is just a normal port filter
+        let single = Opts::builder().port_range(8080..=8080).build();
+        assert_eq!(
+            single,
+            Opts::builder().port(NonZeroU16::new(8080).unwrap()).build()
+        );
+
+        // Merging replaces the port like any other filter
+        assert_eq!(opts.merge(&single), single);
+        assert_eq!(single.merge(&opts), opts);
+
+        let mut relaxed = opts.clone();
+        assert!(relaxed.relax(Filter::Port));
+        assert!(!relaxed.is_filtered());
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = Opts::builder().port_range(9000..=8000).try_build();
+        assert_eq!(
+            empty,
+            Err(OptsError::EmptyPortRange {
+                start: 9000,
+                end: 8000
+            })
+        );
+    }
+
+    #[test]
+    fn transparent_level() {
+        let now = chrono::Utc::now().naive_utc();
+        let proxy_with_level = |level| proxy().with_level(level);
+
+        let opts = Opts::builder().level(Level::Transparent).build();
+        assert!(opts.is_filtered());
+        assert!(opts.allows(&proxy_with_level(Level::Transparent), now));
+        assert!(!opts.allows(&proxy_with_level(Level::Elite), now));
+        assert_eq!(
+            opts.check(Filter::Level, &proxy_with_level(Level::Anonymous), now),
+            Some(false)
+        );
+
+        // Other levels are left to the API
+        let opts = Opts::builder().level(Level::Elite).build();
+        assert!(opts.allows(&proxy_with_level(Level::Anonymous), now));
+    }
+
+    #[test]
+    fn max_age() {
+        use chrono::{Duration as ChronoDuration, NaiveDate};
+
+        let now = NaiveDate::from_ymd_opt(2020, 12, 13)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let proxy_checked_ago = |ago| proxy().with_last_checked(now - ago);
+
+        let six_hours = Opts::builder()
+            .max_age(Duration::from_secs(6 * 60 * 60))
+            .build();
+        assert!(six_hours.is_filtered());
+        assert!(six_hours.allows(&proxy_checked_ago(ChronoDuration::hours(5)), now));
+        assert!(!six_hours.allows(&proxy_checked_ago(ChronoDuration::hours(7)), now));
+        // Too long for the API so it's only filtered locally
+        assert_eq!(six_hours.last_checked, None);
+
+        // Within the API's range gets rounded up to the API's resolution
+        let opts = Opts::builder().max_age(Duration::from_secs(90)).build();
+        assert_eq!(opts.last_checked, Some(2));
+        assert!(opts.allows(&proxy_checked_ago(ChronoDuration::seconds(90)), now));
+        assert!(!opts.allows(&proxy_checked_ago(ChronoDuration::seconds(91)), now));
+        // Unless the API's filter is set explicitly
+        let opts = Opts::builder()
+            .max_age(Duration::from_secs(90))
+            .last_checked(LastChecked::try_from(Duration::from_secs(60 * 10)).unwrap())
+            .build();
+        assert_eq!(opts.last_checked, Some(10));
+
+        // No `max_age` allows anything
+        assert!(Opts::default().allows(&proxy_checked_ago(ChronoDuration::days(365)), now));
+    }
+
+    #[test]
+    fn txt_client_side_filters() {
+        let builder = Opts::builder()
+            .format_txt(true)
+            .max_connect_client_side(Duration::from_millis(500));
+
+        // `txt` proxies are checked when parsed with no time to connect, so they always pass
+        let proxies = proxies_from_txt("1.2.3.4:8080\n5.6.7.8:1080").unwrap();
+        let now = chrono::Utc::now().naive_utc();
+        let six_hours = builder
+            .clone()
+            .max_age(Duration::from_secs(6 * 60 * 60))
+            .build();
+        assert!(proxies.iter().all(|proxy| six_hours.allows(proxy, now)));
+        // leaving the API's filter as the only one that applies, which isn't set here
+        assert_eq!(six_hours.last_checked, None);
+        let opts = builder.max_age(Duration::from_secs(90)).build();
+        assert_eq!(opts.last_checked, Some(2));
+        assert_eq!(opts.time_to_connect, Some(1));
+    }
+
+    #[test]
+    fn exclude_countries() {
+        let now = chrono::Utc::now().naive_utc();
+        let proxy_from = |country| proxy().with_country(country);
+
+        let opts = Opts::builder()
+            .countries(Countries::allow().countries(&[Country::CA, Country::MX, Country::US]))
+            .exclude_countries(&[Country::US])
+            .exclude_countries(&[Country::MX, Country::US])
+            .build();
+        assert!(opts.is_filtered());
+        assert!(opts.allows(&proxy_from(Country::CA), now));
+        assert!(!opts.allows(&proxy_from(Country::MX), now));
+        assert!(!opts.allows(&proxy_from(Country::US), now));
+        assert_eq!(opts.excluded_countries, [Country::US, Country::MX]);
+        // The allowlist still goes to the API as is
+        assert_eq!(
+            opts.to_query_string().unwrap(),
+            "country=CA%2CMX%2CUS&limit=5&format=json"
+        );
+
+        // Exclusions from both sides are kept when merging
+        let merged = Opts::builder()
+            .exclude_countries(&[Country::US])
+            .build()
+            .merge(&Opts::builder().exclude_countries(&[Country::CA]).build());
+        assert_eq!(merged.excluded_countries, [Country::US, Country::CA]);
+
+        // And `any_countries` clears them along with the API's filter
+        let opts = Opts::builder()
+            .exclude_countries(&[Country::US])
+            .any_countries()
+            .build();
+        assert_eq!(opts, Opts::default());
+    }
+
+    #[test]
+    fn max_connect_client_side() {
+        let now = chrono::Utc::now().naive_utc();
+        let proxy_with_connect = |time_to_connect| proxy().with_time_to_connect(time_to_connect);
+
+        let half_sec = Opts::builder()
+            .max_connect_client_side(Duration::from_millis(500))
+            .build();
+        assert!(half_sec.is_filtered());
+        assert!(half_sec.allows(&proxy_with_connect(Duration::from_millis(250)), now));
+        assert!(half_sec.allows(&proxy_with_connect(Duration::from_millis(500)), now));
+        assert!(!half_sec.allows(&proxy_with_connect(Duration::from_millis(501)), now));
+        assert!(!half_sec.allows(&proxy_with_connect(Duration::from_secs(1)), now));
+        // Rounded up to the API's resolution
+        assert_eq!(half_sec.time_to_connect, Some(1));
+
+        // Too long for the API so it's only filtered locally
+        let opts = Opts::builder()
+            .max_connect_client_side(Duration::from_secs(90))
+            .build();
+        assert_eq!(opts.time_to_connect, None);
+        assert!(!opts.allows(&proxy_with_connect(Duration::from_secs(91)), now));
+
+        // Unless the API's filter is set explicitly
+        let opts = Opts::builder()
+            .max_connect_client_side(Duration::from_millis(500))
+            .time_to_connect(TimeToConnect::try_from(Duration::from_secs(10)).unwrap())
+            .build();
+        assert_eq!(opts.time_to_connect, Some(10));
+        assert!(!opts.allows(&proxy_with_connect(Duration::from_secs(5)), now));
+
+        // No threshold allows anything
+        assert!(Opts::default().allows(&proxy_with_connect(Duration::from_secs(60)), now));
+    }
+
+    #[test]
+    fn merge() {
+        let base = Opts::builder()
+            .level(Level::Elite)
+            .countries(Countries::allow().country(Country::CA))
+            .cookies(true)
+            .build();
+
+        // Merging nothing changes nothing
+        assert_eq!(base.merge(&Opts::default()), base);
+
+        // Set fields replace and the same kind of country list gets combined
+        let overrides = Opts::builder()
+            .level(Level::Anonymous)
+            .countries(Countries::allow().countries(&[Country::US, Country::CA]))
+            .post(false)
+            .build();
+        assert_eq!(
+            base.merge(&overrides),
+            Opts::builder()
+                .level(Level::Anonymous)
+                .countries(Countries::allow().countries(&[Country::CA, Country::US]))
+                .cookies(true)
+                .post(false)
+                .build()
+        );
+
+        // While a different kind of list replaces it
+        let blocking = Opts::builder()
+            .countries(Countries::block().country(Country::CH))
+            .build();
+        assert_eq!(
+            base.merge(&blocking).countries,
+            Countries::block().country(Country::CH)
+        );
+
+        // And a key from either side gets the premium limit
+        let premium = Opts::builder().api_key("<key>".to_string()).build();
+        assert_eq!(base.merge(&premium).limit, Limit::Premium);
+        assert_eq!(premium.merge(&base).limit, Limit::Premium);
+        assert_eq!(base.merge(&base).limit, Limit::Free);
+        assert_eq!(base.merge(&premium).per_request_limit(), 20);
+        assert_eq!(base.merge(&base).per_request_limit(), 5);
+
+        // The `txt` format sticks from either side
+        let txt = Opts::builder().lightweight(true).build();
+        assert_eq!(txt.merge(&base).format, Format::Txt);
+        assert_eq!(base.merge(&txt).format, Format::Txt);
+        assert_eq!(base.merge(&base).format, Format::Json);
+    }
+
+    #[test]
+    fn merge_derived_api_filters() {
+        let mins = |mins: u64| Duration::from_secs(mins * 60);
+
+        // An override's `max_age` outside of the API's range drops the base's API filter
+        let base = Opts::builder().max_age(mins(10)).build();
+        assert_eq!(base.last_checked, Some(10));
+        let merged = base.merge(&Opts::builder().max_age(mins(2 * 60)).build());
+        assert_eq!(merged.last_checked, None);
+        assert_eq!(merged.max_age, Some(mins(2 * 60)));
+        // and within it the filter is derived again
+        let merged = base.merge(&Opts::builder().max_age(mins(30)).build());
+        assert_eq!(merged.last_checked, Some(30));
+        // but an explicit filter is kept
+        let explicit = Opts::builder()
+            .last_checked(LastChecked::from_minutes_const(5))
+            .build();
+        let merged = explicit.merge(&Opts::builder().max_age(mins(2 * 60)).build());
+        assert_eq!(merged.last_checked, Some(5));
+
+        // Same goes for the time to connect
+        let base = Opts::builder()
+            .max_connect_client_side(Duration::from_secs(5))
+            .build();
+        assert_eq!(base.time_to_connect, Some(5));
+        let merged = base.merge(
+            &Opts::builder()
+                .max_connect_client_side(Duration::from_secs(2 * 60))
+                .build(),
+        );
+        assert_eq!(merged.time_to_connect, None);
+        let explicit = Opts::builder()
+            .time_to_connect(TimeToConnect::from_secs_const(3))
+            .build();
+        let merged = explicit.merge(
+            &Opts::builder()
+                .max_connect_client_side(Duration::from_secs(2 * 60))
+                .build(),
+        );
+        assert_eq!(merged.time_to_connect, Some(3));
+    }
+
+    #[test]
+    fn check() {
+        use crate::{proxy::Supports, types::Protocol};
+        use chrono::NaiveDate;
+
+        let now = NaiveDate::from_ymd_opt(2020, 12, 13)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let proxy = proxy()
+            .with_port(8080)
+            .with_country(Country::CA)
+            .with_last_checked(now - chrono::Duration::minutes(30))
+            .with_time_to_connect(Duration::from_secs(5))
+            .with_supports(Supports {
+                post: true,
+                ..Supports::default()
+            });
+
+        // Nothing is checked when nothing is set
+        let opts = Opts::default();
+        assert!(Filter::ALL
+            .iter()
+            .all(|&filter| opts.check(filter, &proxy, now).is_none()));
+
+        let opts = Opts::builder()
+            .level(Level::Elite)
+            .protocol(Protocol::Socks5)
+            .countries(Countries::block().countries(&[Country::US, Country::CA]))
+            .last_checked(LastChecked::try_from(Duration::from_secs(60 * 60)).unwrap())
+            .port(NonZeroU16::new(8080).unwrap())
+            .time_to_connect(TimeToConnect::try_from(Duration::from_secs(2)).unwrap())
+            .post(true)
+            .https(true)
+            .build();
+        let checks: Vec<_> = Filter::ALL
+            .iter()
+            .filter_map(|&filter| Some((filter, opts.check(filter, &proxy, now)?)))
+            .collect();
+        assert_eq!(
+            checks,
+            [
+                (Filter::Level, true),
+                (Filter::Protocol, false),
+                (Filter::Countries, false),
+                (Filter::LastChecked, true),
+                (Filter::Port, true),
+                (Filter::TimeToConnect, false),
+                (Filter::Https, false),
+                (Filter::Post, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn relax() {
+        let mut opts = Opts::builder()
+            .level(Level::Elite)
+            .countries(Countries::allow().country(Country::US))
+            .post(true)
+            .build();
+
+        assert!(opts.relax(Filter::Countries));
+        assert!(opts.relax(Filter::Post));
+        // Relaxing a filter that isn't set does nothing
+        assert!(!opts.relax(Filter::Post));
+        assert!(!opts.relax(Filter::Https));
+
+        assert_eq!(opts, Opts::builder().level(Level::Elite).build());
+    }
+
     #[test]
     fn url_serialization() -> Result<(), serde_urlencoded::ser::Error> {
-        let check_equivalent_params = |opts, expected: &[&str]| {
+        let check_equivalent_params = |opts: Opts, expected: &[&str]| {
             // Convert `opts` to a url and sort the values
-            let url = serde_urlencoded::to_string(opts)?;
+            let url = opts.to_query_string()?;
             let mut params: Vec<_> = url.split('&').map(String::from).collect();
             params.sort();
 
@@ -289,23 +2159,25 @@ mod tests {
             Opts::builder().countries(Countries::default()).build(),
             &["format=json", "limit=5"],
         )?;
-        // Kitchen sink
+        // Transparent can't be filtered on by the API so it's left out
+        check_equivalent_params(
+            Opts::builder().level(Level::Transparent).build(),
+            &["format=json", "limit=5"],
+        )?;
+        // Multiple protocols are requested one at a time
         check_equivalent_params(
             Opts::builder()
-                .api_key("<key>".to_string())
-                .level(Level::Elite)
-                .protocol(Protocol::Socks4)
-                .countries(Countries::block().countries(&[Country::CH, Country::ES]))
-                .last_checked(LastChecked::try_from(Duration::from_secs(60 * 10)).unwrap())
-                .time_to_connect(TimeToConnect::try_from(Duration::from_secs(10)).unwrap())
-                .port(NonZeroU16::new(8080).unwrap())
-                .cookies(true)
-                .connects_to_google(false)
-                .https(true)
-                .post(false)
-                .referer(true)
-                .forwards_user_agent(false)
+                .protocols(&[Protocol::Http, Protocol::Socks5])
                 .build(),
+            &["format=json", "limit=5"],
+        )?;
+        check_equivalent_params(
+            Opts::builder().format_txt(true).build(),
+            &["format=txt", "limit=5"],
+        )?;
+        // Kitchen sink
+        check_equivalent_params(
+            kitchen_sink(),
             &[
                 // Automatic
                 "limit=20",
@@ -331,4 +2203,64 @@ mod tests {
             ],
         )
     }
+
+    fn kitchen_sink() -> Opts {
+        Opts::builder()
+            .api_key("<key>".to_string())
+            .level(Level::Elite)
+            .protocol(Protocol::Socks4)
+            .countries(Countries::block().countries(&[Country::CH, Country::ES]))
+            .last_checked(LastChecked::try_from(Duration::from_secs(60 * 10)).unwrap())
+            .time_to_connect(TimeToConnect::try_from(Duration::from_secs(10)).unwrap())
+            .port(NonZeroU16::new(8080).unwrap())
+            .cookies(true)
+            .connects_to_google(false)
+            .https(true)
+            .post(false)
+            .referer(true)
+            .forwards_user_agent(false)
+            .build()
+    }
+
+    #[test]
+    fn url_deserialization() -> Result<(), serde_urlencoded::de::Error> {
+        // Round trips
+        let round_trip = |opts: Opts| {
+            let query = opts.to_query_string().unwrap();
+            assert_eq!(Opts::from_query_string(&query)?, opts);
+            Ok(())
+        };
+        round_trip(Opts::default())?;
+        round_trip(Opts::builder().format_txt(true).build())?;
+        round_trip(
+            Opts::builder()
+                .countries(Countries::allow().countries(&[Country::US, Country::CA]))
+                .build(),
+        )?;
+        round_trip(kitchen_sink())?;
+
+        // Full URLs work as well
+        let url = format!(
+            "{}{}",
+            constants::API_URI,
+            kitchen_sink().to_query_string().unwrap()
+        );
+        assert_eq!(Opts::from_query_string(&url)?, kitchen_sink());
+
+        // And anything invalid is an error
+        for invalid in &[
+            "country=US&not_country=CA",
+            "country=ZZ",
+            "last_check=0",
+            "speed=61",
+            "port=0",
+            "format=xml",
+            "level=over9000",
+            "unknown=param",
+        ] {
+            assert!(Opts::from_query_string(invalid).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
 }
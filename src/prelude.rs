@@ -0,0 +1,21 @@
+//! Re-exports the types needed for most programs so they can be brought in with a single `use`.
+//!
+//! ```rust,no_run
+//! use lead_oxide::prelude::*;
+//!
+//! fn main() -> Result<(), ApiError> {
+//!     let mut fetcher = Fetcher::new(Opts::builder().level(Level::Elite).build());
+//!     let proxies: Vec<Proxy> = fetcher.try_get(5)?;
+//!     println!("{:#?}", proxies);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+pub use crate::{
+    errors::{ApiError, ParamError},
+    fetcher::Fetcher,
+    opts::Opts,
+    proxy::Proxy,
+    types::{Countries, LastChecked, Level, Protocol, TimeToConnect},
+};
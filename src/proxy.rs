@@ -1,30 +1,52 @@
 //! [`Proxy`][Proxy]s represent information about the proxies returned by
 //! [`Fetcher`][crate::fetcher::Fetcher].
 
-use std::{net::SocketAddrV4, time::Duration};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::HashMap,
+    fmt, io,
+    net::{AddrParseError, SocketAddr, TcpStream},
+    time::Duration,
+};
+
+use std::convert::TryFrom;
+
+#[cfg(feature = "check")]
+use std::{
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+    thread,
+    time::Instant,
+};
 
+#[cfg(feature = "check")]
+use crate::{errors::CheckError, fetcher::transport::set_timeouts};
 use crate::{
-    constants::REPO_URI,
+    errors::{ParamError, ProxyParseError},
+    opts::{Filter, Opts},
     types::{Level, Protocol},
 };
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime};
 use iso_country::Country;
-use serde::{de::Deserializer, Deserialize};
+use serde::{
+    de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor},
+    Deserialize, Serialize, Serializer,
+};
 
 /// Internal
 #[derive(Deserialize, Clone, Debug, PartialEq)]
-struct Response {
-    pub data: Vec<RawProxy>,
+struct Response<T> {
+    pub data: Vec<T>,
 }
 
 /// Internal
 #[derive(Deserialize, Clone, Debug, PartialEq)]
-struct RawProxy {
-    #[serde(rename = "ipPort")]
-    socket: SocketAddrV4,
+struct TypedProxy {
+    #[serde(rename = "ipPort", deserialize_with = "deserialize_socket")]
+    socket: SocketAddr,
     #[serde(deserialize_with = "ignore_bad_countries")]
     country: Country,
+    // These are checked separately so that a bad value only skips the one proxy
     last_checked: String,
     #[serde(rename = "proxy_level")]
     level: Level,
@@ -36,6 +58,22 @@ struct RawProxy {
     supports: RawSupports,
 }
 
+// IPv6 sockets have to be bracketed like `[::1]:8080` since without brackets there's no telling
+// whether the last group is part of the address or the port
+/// Internal
+fn parse_socket(s: &str) -> Result<SocketAddr, AddrParseError> {
+    s.parse()
+}
+
+/// Internal
+fn deserialize_socket<'de, D>(deserializer: D) -> Result<SocketAddr, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse_socket(&s).map_err(|_| de::Error::custom(format!("invalid socket: '{}'", s)))
+}
+
 // Sometimes country codes other than iso 3166-1 are returned so switch those to unspecified
 /// Internal
 fn ignore_bad_countries<'de, D>(deserializer: D) -> Result<Country, D::Error>
@@ -45,81 +83,830 @@ where
     Deserialize::deserialize(deserializer).or(Ok(Country::Unspecified))
 }
 
+// The API currently uses `%F %T`, but be lenient in case that drifts
+/// Internal
+fn parse_last_checked(s: &str) -> Option<NaiveDateTime> {
+    const FORMATS: &[&str] = &["%F %T", "%FT%T"];
+
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|date_time| date_time.naive_utc())
+        })
+}
+
+/// Internal
+fn try_last_checked(s: &str) -> Result<NaiveDateTime, ProxyParseError> {
+    parse_last_checked(s).ok_or_else(|| ProxyParseError::new("last_checked", s))
+}
+
+/// Internal
+fn try_time_to_connect(s: &str) -> Result<Duration, ProxyParseError> {
+    s.parse()
+        .map(Duration::from_secs)
+        .map_err(|_| ProxyParseError::new("speed", s))
+}
+
+/// Internal
+fn deserialize_last_checked<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    try_last_checked(&s).map_err(de::Error::custom)
+}
+
 /// Internal
-#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
-struct RawSupports {
-    https: Option<u8>,
-    get: Option<u8>,
-    post: Option<u8>,
-    cookies: Option<u8>,
-    referer: Option<u8>,
-    #[serde(rename = "user_agent")]
-    forwards_user_agent: Option<u8>,
-    #[serde(rename = "google")]
-    connects_to_google: Option<u8>,
+fn serialize_last_checked<S: Serializer>(
+    last_checked: &NaiveDateTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&last_checked.format("%F %T"))
+}
+
+/// Internal
+fn serialize_secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_secs())
+}
+
+/// Internal
+fn deserialize_secs<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_secs)
+}
+
+/// A proxy exactly as it was returned by the API.
+///
+/// Every field the API returns is kept as is without any of the parsing or validation done for
+/// [`Proxy`][Proxy], including any fields that aren't used by `Proxy` at all. Field names match
+/// the API's with any unexpected fields collected in `extra`. This is returned by
+/// [`Fetcher::try_get_raw_proxies`][crate::fetcher::Fetcher::try_get_raw_proxies].
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RawProxy {
+    #[serde(rename = "ipPort")]
+    pub ip_port: String,
+    pub ip: String,
+    pub port: String,
+    pub country: String,
+    pub last_checked: String,
+    pub proxy_level: String,
+    pub r#type: String,
+    pub speed: String,
+    pub support: RawSupports,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// The supported attributes of a [`RawProxy`][RawProxy] as returned by the API where `1` means
+/// supported.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawSupports {
+    pub https: Option<u8>,
+    pub get: Option<u8>,
+    pub post: Option<u8>,
+    pub cookies: Option<u8>,
+    pub referer: Option<u8>,
+    pub user_agent: Option<u8>,
+    pub google: Option<u8>,
 }
 
 /// All the information representing a proxy.
 ///
 /// Typically most people will likely only use the `socket` value, but this contains all the
 /// information on a proxy.
-#[derive(Clone, Debug, PartialEq, Eq)]
+///
+/// Proxies can be serialized to store them for later, like saving them to disk as JSON. The
+/// `last_checked` time is stored in the same `%F %T` format the API uses and `time_to_connect` is
+/// stored in seconds.
+///
+/// ```
+/// use lead_oxide::proxy::Proxy;
+///
+/// let json = r#"{
+///     "socket": "1.2.3.4:8080",
+///     "country": "US",
+///     "last_checked": "2020-12-13 01:23:45",
+///     "level": "elite",
+///     "protocol": "http",
+///     "time_to_connect": 3,
+///     "supports": {
+///         "https": true,
+///         "get": true,
+///         "post": false,
+///         "cookies": false,
+///         "referer": true,
+///         "forwards_user_agent": true,
+///         "connects_to_google": false
+///     }
+/// }"#;
+/// let proxy: Proxy = serde_json::from_str(json).unwrap();
+/// assert_eq!(proxy, serde_json::from_str(&serde_json::to_string(&proxy).unwrap()).unwrap());
+/// ```
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct Proxy {
-    pub socket: SocketAddrV4,
+    pub socket: SocketAddr,
     pub country: Country,
+    #[serde(
+        serialize_with = "serialize_last_checked",
+        deserialize_with = "deserialize_last_checked"
+    )]
     pub last_checked: NaiveDateTime,
     pub level: Level,
     pub protocol: Protocol,
+    #[serde(
+        serialize_with = "serialize_secs",
+        deserialize_with = "deserialize_secs"
+    )]
     pub time_to_connect: Duration,
     pub supports: Supports,
 }
 
-impl From<RawProxy> for Proxy {
-    fn from(raw: RawProxy) -> Self {
-        let last_checked = NaiveDateTime::parse_from_str(&raw.last_checked, "%F %T")
-            .unwrap_or_else(|_| {
-                panic!(
-                    "The API returned an invalid time. Please raise an issue to address this at {}",
-                    REPO_URI
-                )
+impl Proxy {
+    /// Opens a TCP connection to the proxy, giving up after `timeout`.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    /// use std::time::Duration;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let proxy = &fetcher.try_get(1).unwrap()[0];
+    /// let stream = proxy.connect(Duration::from_secs(5)).unwrap();
+    /// ```
+    pub fn connect(&self, timeout: Duration) -> io::Result<TcpStream> {
+        TcpStream::connect_timeout(&self.socket, timeout)
+    }
+
+    /// Checks that the proxy currently works by sending a GET request to `target` through it,
+    /// where `timeout` applies to connecting to the proxy and to each step of the request. The
+    /// API's `last_checked` can be stale so this is handy right before using a proxy. Any response
+    /// from `target` counts as reaching it, so check the [`CheckReport`][CheckReport]'s status
+    /// for anything more specific. Only HTTP and SOCKS5 proxies with an IPv4 address can be
+    /// checked.
+    ///
+    /// This is only available with the `check` feature enabled.
+    ///
+    /// ```no_run
+    /// use lead_oxide::{fetcher::Fetcher, opts::Opts, types::Protocol};
+    /// use std::time::Duration;
+    ///
+    /// let mut fetcher = Fetcher::new(Opts::builder().protocol(Protocol::Http).build());
+    /// let proxy = &fetcher.try_get(1).unwrap()[0];
+    /// let report = proxy
+    ///     .check("http://example.com", Duration::from_secs(5))
+    ///     .unwrap();
+    /// println!(
+    ///     "Connected in {:?} (the API claimed {:?})",
+    ///     report.time_to_connect, proxy.time_to_connect
+    /// );
+    /// ```
+    #[cfg(feature = "check")]
+    pub fn check(&self, target: &str, timeout: Duration) -> Result<CheckReport, CheckError> {
+        // `ureq` doesn't support SOCKS4 proxies, and it can't parse proxies with an IPv6 address
+        let scheme = match self.protocol {
+            Protocol::Http => "http",
+            Protocol::Socks5 => "socks5",
+            Protocol::Socks4 => {
+                return Err(CheckError::Unsupported {
+                    what: "SOCKS4 proxies",
+                })
+            }
+        };
+        if self.socket.is_ipv6() {
+            return Err(CheckError::Unsupported {
+                what: "IPv6 proxies",
             });
+        }
 
-        let secs_to_connect = raw.time_to_connect.parse().unwrap_or_else(|_| {
-            panic!(
-                "The API returned an invalid int. Please raise an issue to address this at {}",
-                REPO_URI
-            )
-        });
-        let time_to_connect = Duration::from_secs(secs_to_connect);
+        let start = Instant::now();
+        self.connect(timeout)
+            .map_err(|source| CheckError::Connect { source })?;
+        let time_to_connect = start.elapsed();
 
-        Self {
-            socket: raw.socket,
-            country: raw.country,
-            last_checked,
-            level: raw.level,
-            protocol: raw.protocol,
-            time_to_connect,
-            supports: Supports::from(raw.supports),
+        let proxy = ureq::Proxy::new(format!("{}://{}", scheme, self.socket)).map_err(|err| {
+            CheckError::Request {
+                message: err.to_string(),
+            }
+        })?;
+        let mut request = ureq::get(target);
+        request.set_proxy(proxy);
+        set_timeouts(&mut request, timeout);
+
+        let start = Instant::now();
+        let resp = request.call();
+        let latency = start.elapsed();
+        if resp.synthetic() {
+            let message = resp
+                .into_synthetic_error()
+                .map(|err| err.to_string())
+                .unwrap_or_default();
+            Err(CheckError::Request { message })
+        } else {
+            Ok(CheckReport {
+                status: resp.status(),
+                latency,
+                time_to_connect,
+            })
+        }
+    }
+
+    /// Formats the proxy as a URL with a scheme matching its protocol, like `socks5://1.2.3.4:1080`
+    /// or `http://[2001:db8::1]:8080` for IPv6. This is the format most HTTP clients expect when
+    /// setting a proxy.
+    ///
+    /// ```no_run
+    /// use lead_oxide::fetcher::Fetcher;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let proxy = &fetcher.try_get(1).unwrap()[0];
+    /// println!("Connect through {}", proxy.to_url());
+    /// ```
+    pub fn to_url(&self) -> String {
+        // The protocol names happen to match their URL schemes
+        format!("{}://{}", self.protocol, self.socket)
+    }
+
+    /// Returns `true` if the proxy was last checked more than `max_age` before `now`. A proxy
+    /// checked after `now` is never stale.
+    ///
+    /// ```
+    /// use chrono::Duration as ChronoDuration;
+    /// use lead_oxide::proxy::Proxy;
+    /// use std::time::Duration;
+    ///
+    /// # fn run(proxy: Proxy) {
+    /// let now = proxy.last_checked + ChronoDuration::minutes(10);
+    /// assert!(proxy.is_stale(Duration::from_secs(5 * 60), now));
+    /// assert!(!proxy.is_stale(Duration::from_secs(10 * 60), now));
+    /// # }
+    /// ```
+    pub fn is_stale(&self, max_age: Duration, now: NaiveDateTime) -> bool {
+        match (now - self.last_checked).to_std() {
+            Ok(age) => age > max_age,
+            // Checked in the future is as fresh as it gets
+            Err(_) => false,
+        }
+    }
+
+    /// Like [`is_stale`][Proxy::is_stale], but compared to the current time in UTC which is what
+    /// the API uses for `last_checked`.
+    pub fn is_stale_utc(&self, max_age: Duration) -> bool {
+        self.is_stale(max_age, chrono::Utc::now().naive_utc())
+    }
+
+    /// Returns `true` if the proxy matches every filter set on `opts`, including client-side
+    /// filters like [`max_age`][crate::opts::OptsBuilder::max_age]. When `opts` use the `txt`
+    /// format (see [`lightweight`][crate::opts::OptsBuilder::lightweight]) the country, age, and
+    /// time to connect are left out of the response, so filters on those always pass.
+    ///
+    /// ```no_run
+    /// use lead_oxide::{fetcher::Fetcher, opts::Opts};
+    ///
+    /// let opts = Opts::builder().post(true).build();
+    /// let mut fetcher = Fetcher::new(opts.clone());
+    /// let proxies = fetcher.try_get(5).unwrap();
+    /// assert!(proxies.iter().all(|proxy| proxy.satisfies(&opts)));
+    /// ```
+    pub fn satisfies(&self, opts: &Opts) -> bool {
+        let now = chrono::Utc::now().naive_utc();
+        opts.allows(self, now)
+            && Filter::ALL
+                .iter()
+                .all(|&filter| opts.check(filter, self, now) != Some(false))
+    }
+}
+
+/// Formats a compact summary of the proxy on a single line as
+/// `{socket} ({protocol}, {level}, {country}, {time_to_connect}s)` where the protocol and level use
+/// the same lowercase names as the API and the country is the two letter country code, or `??`
+/// when it's unknown.
+///
+/// ```
+/// # use chrono::NaiveDate;
+/// # use iso_country::Country;
+/// # use lead_oxide::{proxy::{Proxy, Supports}, types::{Level, Protocol}};
+/// # use std::time::Duration;
+/// let proxy = Proxy {
+///     socket: "1.2.3.4:8080".parse().unwrap(),
+///     country: Country::US,
+///     # last_checked: NaiveDate::from_ymd_opt(2020, 12, 13).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+///     level: Level::Elite,
+///     protocol: Protocol::Socks5,
+///     time_to_connect: Duration::from_secs(3),
+///     // ...
+///     # supports: Supports::default(),
+/// };
+/// assert_eq!(proxy.to_string(), "1.2.3.4:8080 (socks5, elite, US, 3s)");
+/// ```
+impl fmt::Display for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let country = match self.country {
+            Country::Unspecified => "??".to_string(),
+            country => country.to_string(),
+        };
+
+        write!(
+            f,
+            "{} ({}, {}, {}, {}s)",
+            self.socket,
+            self.protocol,
+            self.level,
+            country,
+            self.time_to_connect.as_secs()
+        )
+    }
+}
+
+/// The results from successfully checking a proxy with [`Proxy::check`][Proxy::check].
+///
+/// This is only available with the `check` feature enabled.
+#[cfg(feature = "check")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckReport {
+    /// The status code of `target`'s response.
+    pub status: u16,
+    /// How long the request through the proxy took.
+    pub latency: Duration,
+    /// How long connecting to the proxy took, to compare against what the API reported.
+    pub time_to_connect: Duration,
+}
+
+#[cfg(feature = "check")]
+impl CheckReport {
+    /// Returns `true` if `target` responded with a success status code.
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Picks the matching `reqwest::Proxy` constructor for the proxy's protocol. Proxies are only used
+/// for HTTPS traffic if they report supporting HTTPS, or if they're a SOCKS proxy (which requires
+/// `reqwest`'s `socks` feature).
+///
+/// This is only available with the `reqwest` feature enabled.
+#[cfg(feature = "reqwest")]
+impl TryFrom<&Proxy> for reqwest::Proxy {
+    type Error = reqwest::Error;
+
+    fn try_from(proxy: &Proxy) -> Result<Self, Self::Error> {
+        let url = proxy.to_url();
+        match proxy.protocol {
+            Protocol::Http if proxy.supports.https => reqwest::Proxy::all(url),
+            Protocol::Http => reqwest::Proxy::http(url),
+            Protocol::Socks4 | Protocol::Socks5 => reqwest::Proxy::all(url),
+        }
+    }
+}
+
+impl TryFrom<TypedProxy> for Proxy {
+    type Error = ProxyParseError;
+
+    fn try_from(typed: TypedProxy) -> Result<Self, Self::Error> {
+        Ok(Self {
+            socket: typed.socket,
+            country: typed.country,
+            last_checked: try_last_checked(&typed.last_checked)?,
+            level: typed.level,
+            protocol: typed.protocol,
+            time_to_connect: try_time_to_connect(&typed.time_to_connect)?,
+            supports: Supports::from(typed.supports),
+        })
+    }
+}
+
+/// Does the same parsing that's done for the proxies returned by
+/// [`Fetcher`][crate::fetcher::Fetcher], returning the first field that couldn't be parsed. Like
+/// usual, a country that isn't an ISO 3166-1 code becomes `Country::Unspecified`.
+impl TryFrom<RawProxy> for Proxy {
+    type Error = ProxyParseError;
+
+    fn try_from(raw: RawProxy) -> Result<Self, Self::Error> {
+        Ok(Self {
+            socket: parse_socket(&raw.ip_port)
+                .map_err(|_| ProxyParseError::new("ipPort", &raw.ip_port))?,
+            country: raw.country.parse().unwrap_or(Country::Unspecified),
+            last_checked: try_last_checked(&raw.last_checked)?,
+            level: raw
+                .proxy_level
+                .parse()
+                .map_err(|_| ProxyParseError::new("proxy_level", &raw.proxy_level))?,
+            protocol: raw
+                .r#type
+                .parse()
+                .map_err(|_| ProxyParseError::new("type", &raw.r#type))?,
+            time_to_connect: try_time_to_connect(&raw.speed)?,
+            supports: Supports::from(raw.support),
+        })
+    }
+}
+
+/// Internal
+pub(crate) fn proxies_from_json(
+    json: &str,
+    keep_unknown: bool,
+) -> Result<Vec<Proxy>, serde_json::Error> {
+    parse_proxies(serde_json::Deserializer::from_str(json), keep_unknown)
+}
+
+/// Parses proxies from an API response as it's read from `reader` instead of from a fully
+/// buffered `String`. Proxies with an unknown country are dropped as they're parsed unless
+/// `keep_unknown` is set (see
+/// [`OptsBuilder::keep_unknown_countries`][crate::opts::OptsBuilder::keep_unknown_countries]), so
+/// only the proxies that are kept ever get collected. Proxies with a `last_checked` or `speed`
+/// that can't be parsed are skipped too. This is handy for large premium responses or responses
+/// that were saved to disk.
+///
+/// Peak memory scales with the proxies that are kept rather than every proxy in the response. For
+/// a 10,000 proxy response where half have an unknown country, `benches/streaming.rs` measured a
+/// peak of 864 KiB compared to 1,728 KiB when collecting everything before filtering, and parsing
+/// took about 30% less time.
+///
+/// ```no_run
+/// use lead_oxide::proxy::proxies_from_json_streaming;
+/// use std::fs::File;
+///
+/// let file = File::open("response.json").unwrap();
+/// let proxies = proxies_from_json_streaming(file, false).unwrap();
+/// ```
+pub fn proxies_from_json_streaming<R: io::Read>(
+    reader: R,
+    keep_unknown: bool,
+) -> Result<Vec<Proxy>, serde_json::Error> {
+    parse_proxies(serde_json::Deserializer::from_reader(reader), keep_unknown)
+}
+
+/// Internal
+fn parse_proxies<'de, R: serde_json::de::Read<'de>>(
+    mut deserializer: serde_json::Deserializer<R>,
+    keep_unknown: bool,
+) -> Result<Vec<Proxy>, serde_json::Error> {
+    let proxies = ResponseSeed { keep_unknown }.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(proxies)
+}
+
+/// Internal
+// Picks the `data` out of a response while ignoring everything else
+struct ResponseSeed {
+    keep_unknown: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for ResponseSeed {
+    type Value = Vec<Proxy>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ResponseSeed {
+    type Value = Vec<Proxy>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a response with a list of proxies")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut data = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "data" {
+                data = Some(map.next_value_seed(DataSeed {
+                    keep_unknown: self.keep_unknown,
+                })?);
+            } else {
+                map.next_value::<de::IgnoredAny>()?;
+            }
+        }
+
+        data.ok_or_else(|| de::Error::missing_field("data"))
+    }
+}
+
+/// Internal
+// Filters the proxies as they're deserialized so the dropped ones are never collected
+struct DataSeed {
+    keep_unknown: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for DataSeed {
+    type Value = Vec<Proxy>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DataSeed {
+    type Value = Vec<Proxy>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of proxies")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut proxies = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(typed) = seq.next_element::<TypedProxy>()? {
+            // Just to play it safe we filter out any results with an incorrect country field by
+            // default. This is typically less than 10% of responses, so callers that don't care
+            // about the location can choose to keep them.
+            if self.keep_unknown || typed.country != Country::Unspecified {
+                // One bad proxy shouldn't throw out the rest of the response
+                let socket = typed.socket;
+                match Proxy::try_from(typed) {
+                    Ok(proxy) => proxies.push(proxy),
+                    Err(err) => debug!("Skipping the proxy at {}: {}", socket, err),
+                }
+            }
         }
+
+        Ok(proxies)
     }
 }
 
+/// Parses proxies from the API's `txt` format which is just one `ip:port` socket per line. A line
+/// can also start with the proxy's scheme like `socks5://1.2.3.4:1080` to set its protocol.
+///
+/// None of the other information on a proxy is included in this format, so every `Proxy` gets
+/// filled in with defaults: `Country::Unspecified`, `Level::Transparent`, `Protocol::Http`, a
+/// `last_checked` of when it was parsed, a `time_to_connect` of zero, and no supported attributes.
+/// [`Fetcher`][crate::fetcher::Fetcher] will also fill in anything it filtered on (see
+/// [`OptsBuilder::format_txt`][crate::opts::OptsBuilder::format_txt]). Note that this means these
+/// proxies always look fresh and fast, so checking their age or time to connect tells you
+/// nothing.
+///
+/// ```
+/// use lead_oxide::{proxy::proxies_from_txt, types::Protocol};
+///
+/// let proxies = proxies_from_txt("1.2.3.4:8080\nsocks5://5.6.7.8:1080\n").unwrap();
+/// assert_eq!(proxies.len(), 2);
+/// assert_eq!(proxies[1].socket.port(), 1080);
+/// assert_eq!(proxies[1].protocol, Protocol::Socks5);
+/// ```
+pub fn proxies_from_txt(txt: &str) -> Result<Vec<Proxy>, AddrParseError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    txt.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (protocol, socket) = match line.split_once("://") {
+                Some((scheme, socket)) => match scheme.parse() {
+                    Ok(protocol) => (protocol, socket),
+                    // Leaves the whole line to fail as a socket
+                    Err(_) => (Protocol::Http, line),
+                },
+                None => (Protocol::Http, line),
+            };
+
+            Ok(Proxy {
+                socket: parse_socket(socket)?,
+                country: Country::Unspecified,
+                last_checked: now,
+                level: Level::Transparent,
+                protocol,
+                time_to_connect: Duration::from_secs(0),
+                supports: Supports::default(),
+            })
+        })
+        .collect()
+}
+
 /// Internal
-pub(crate) fn proxies_from_json(json: &str) -> Result<Vec<Proxy>, serde_json::Error> {
-    let resp: Response = serde_json::from_str(json)?;
-    Ok(resp
-        .data
+pub(crate) fn raw_proxies_from_json(json: &str) -> Result<Vec<RawProxy>, serde_json::Error> {
+    let resp: Response<RawProxy> = serde_json::from_str(json)?;
+    Ok(resp.data)
+}
+
+/// Merges proxies that share the same `socket`, keeping whichever was checked most recently. This
+/// is useful for cleaning up after combining proxies from different sources, like a
+/// [`ValidatedCache`][crate::cache::ValidatedCache] alongside freshly fetched ones. The order of
+/// the first appearance of each socket is kept.
+///
+/// ```no_run
+/// use lead_oxide::{fetcher::Fetcher, proxy::merge_freshest};
+///
+/// let mut fetcher = Fetcher::default();
+/// let mut proxies = fetcher.try_get(5).unwrap();
+/// proxies.append(&mut fetcher.try_get(5).unwrap());
+/// let unique = merge_freshest(proxies);
+/// ```
+pub fn merge_freshest(proxies: Vec<Proxy>) -> Vec<Proxy> {
+    let mut merged: Vec<Proxy> = Vec::with_capacity(proxies.len());
+    let mut positions: HashMap<SocketAddr, usize> = HashMap::new();
+
+    for proxy in proxies {
+        match positions.get(&proxy.socket) {
+            Some(&i) => {
+                if proxy.last_checked > merged[i].last_checked {
+                    merged[i] = proxy;
+                }
+            }
+            None => {
+                positions.insert(proxy.socket, merged.len());
+                merged.push(proxy);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Splits `proxies` up by their `country`, keeping the order they were in within each country.
+/// Proxies with an unknown country are grouped under `Country::Unspecified`.
+///
+/// ```no_run
+/// use iso_country::Country;
+/// use lead_oxide::{fetcher::Fetcher, proxy::group_by_country};
+///
+/// let mut fetcher = Fetcher::default();
+/// let by_country = group_by_country(fetcher.try_get(10).unwrap());
+/// if let Some(us_proxies) = by_country.get(&Country::US) {
+///     println!("{} proxies in the US", us_proxies.len());
+/// }
+/// ```
+pub fn group_by_country(proxies: Vec<Proxy>) -> HashMap<Country, Vec<Proxy>> {
+    let mut groups: HashMap<Country, Vec<Proxy>> = HashMap::new();
+    for proxy in proxies {
+        groups.entry(proxy.country).or_default().push(proxy);
+    }
+
+    groups
+}
+
+/// Writes `proxies` to `writer` as JSON lines, where each line is a single [`Proxy`][Proxy]
+/// serialized the same as `serde_json` would. These fields are kept stable so that other tools can
+/// rely on them:
+///
+/// - `socket`: the `ip:port` with IPv6 addresses in brackets like `[2001:db8::1]:8080`
+/// - `country`: the two letter country code, or an empty string if it's unknown
+/// - `last_checked`: in the `%F %T` format the API uses like `"2020-12-13 01:23:45"`
+/// - `level`: one of `"anonymous"`, `"elite"`, or `"transparent"`
+/// - `protocol`: one of `"http"`, `"socks4"`, or `"socks5"`
+/// - `time_to_connect`: in whole seconds
+/// - `supports`: an object with a boolean for each [`Supports`][Supports] field
+///
+/// ```no_run
+/// use lead_oxide::{fetcher::Fetcher, proxy::write_jsonl};
+/// use std::fs::File;
+///
+/// let mut fetcher = Fetcher::default();
+/// let proxies = fetcher.try_get(5).unwrap();
+/// write_jsonl(&proxies, File::create("proxies.jsonl").unwrap()).unwrap();
+/// ```
+pub fn write_jsonl<W: io::Write>(proxies: &[Proxy], mut writer: W) -> io::Result<()> {
+    for proxy in proxies {
+        serde_json::to_writer(&mut writer, proxy)?;
+        writeln!(writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Reads back proxies written by [`write_jsonl`][write_jsonl]. Blank lines are skipped, and a line
+/// that isn't a valid proxy is an `io::ErrorKind::InvalidData` error.
+///
+/// ```no_run
+/// use lead_oxide::proxy::read_jsonl;
+/// use std::{fs::File, io::BufReader};
+///
+/// let file = File::open("proxies.jsonl").unwrap();
+/// let proxies = read_jsonl(BufReader::new(file)).unwrap();
+/// ```
+pub fn read_jsonl<R: io::BufRead>(reader: R) -> io::Result<Vec<Proxy>> {
+    let mut proxies = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            proxies.push(serde_json::from_str(&line)?);
+        }
+    }
+
+    Ok(proxies)
+}
+
+/// Writes `proxies` to `writer` as CSV with a header row. The columns are `ip`, `port`,
+/// `protocol`, `level`, `country`, `last_checked`, `seconds_to_connect`, and then one column for
+/// each of the [`Supports`][Supports] attributes. Unknown countries are left empty.
+///
+/// This is only available with the `csv` feature enabled.
+///
+/// ```no_run
+/// use lead_oxide::{fetcher::Fetcher, proxy::to_csv};
+/// use std::fs::File;
+///
+/// let mut fetcher = Fetcher::default();
+/// let proxies = fetcher.try_get(5).unwrap();
+/// to_csv(&proxies, File::create("proxies.csv").unwrap()).unwrap();
+/// ```
+#[cfg(feature = "csv")]
+pub fn to_csv<W: io::Write>(proxies: &[Proxy], mut writer: W) -> io::Result<()> {
+    // None of the values can contain a comma, quote, or newline so nothing needs escaping
+    const COLUMNS: [&str; 7] = [
+        "ip",
+        "port",
+        "protocol",
+        "level",
+        "country",
+        "last_checked",
+        "seconds_to_connect",
+    ];
+    let header: Vec<_> = COLUMNS
+        .iter()
+        .chain(Supports::NAMES.iter())
+        .copied()
+        .collect();
+    writeln!(writer, "{}", header.join(","))?;
+
+    for proxy in proxies {
+        let country = match proxy.country {
+            Country::Unspecified => String::new(),
+            country => country.to_string(),
+        };
+        write!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            proxy.socket.ip(),
+            proxy.socket.port(),
+            proxy.protocol,
+            proxy.level,
+            country,
+            proxy.last_checked.format("%F %T"),
+            proxy.time_to_connect.as_secs_f64(),
+        )?;
+        for supported in &proxy.supports.fields() {
+            write!(writer, ",{}", supported)?;
+        }
+        writeln!(writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Checks every proxy with [`Proxy::check`][Proxy::check] using up to `concurrency` threads at
+/// once, keeping only the proxies where `target` responded with a success status code. The
+/// working proxies stay in the same order they were passed in.
+///
+/// This is only available with the `check` feature enabled.
+///
+/// ```no_run
+/// use lead_oxide::{fetcher::Fetcher, opts::Opts, proxy::filter_working, types::Protocol};
+/// use std::time::Duration;
+///
+/// let mut fetcher = Fetcher::new(Opts::builder().protocol(Protocol::Http).build());
+/// let proxies = fetcher.try_get(10).unwrap();
+/// let working = filter_working(proxies, "http://example.com", 4, Duration::from_secs(5));
+/// ```
+#[cfg(feature = "check")]
+pub fn filter_working(
+    proxies: Vec<Proxy>,
+    target: &str,
+    concurrency: usize,
+    timeout: Duration,
+) -> Vec<Proxy> {
+    // Each thread keeps taking the next unchecked proxy until there are none left
+    let next = AtomicUsize::new(0);
+    let mut working = vec![false; proxies.len()];
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..concurrency.clamp(1, proxies.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut checked = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, AtomicOrdering::SeqCst);
+                        let proxy = match proxies.get(i) {
+                            Some(proxy) => proxy,
+                            None => break checked,
+                        };
+                        let ok = matches!(proxy.check(target, timeout), Ok(report) if report.ok());
+                        checked.push((i, ok));
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            let checked = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (i, ok) in checked {
+                working[i] = ok;
+            }
+        }
+    });
+
+    proxies
         .into_iter()
-        .map(Proxy::from)
-        // Just to play it safe we filter out any results with an incorrect country field. We could
-        // be smarter and only use this in the presence of a blocklist if this causes issues. Just
-        // to note this is typically less than 10% or responses.
-        .filter(|&Proxy { country, .. }| country != Country::Unspecified)
-        .collect())
+        .zip(working)
+        .filter_map(|(proxy, ok)| if ok { Some(proxy) } else { None })
+        .collect()
 }
 
 /// Represents all the attributes that the [`Proxy`][Proxy] supports.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Supports {
     pub https: bool,
     pub get: bool,
@@ -130,6 +917,162 @@ pub struct Supports {
     pub connects_to_google: bool,
 }
 
+impl Supports {
+    const NAMES: [&'static str; 7] = [
+        "https",
+        "get",
+        "post",
+        "cookies",
+        "referer",
+        "forwards_user_agent",
+        "connects_to_google",
+    ];
+
+    fn fields(&self) -> [bool; 7] {
+        [
+            self.https,
+            self.get,
+            self.post,
+            self.cookies,
+            self.referer,
+            self.forwards_user_agent,
+            self.connects_to_google,
+        ]
+    }
+
+    /// The names of all the supported attributes. The names match the field names.
+    ///
+    /// ```
+    /// use lead_oxide::proxy::Supports;
+    ///
+    /// let supports = Supports { https: true, post: true, ..Supports::default() };
+    /// assert_eq!(supports.enabled_names(), ["https", "post"]);
+    /// ```
+    pub fn enabled_names(&self) -> Vec<&'static str> {
+        Self::NAMES
+            .iter()
+            .zip(self.fields().iter())
+            .filter(|(_, &enabled)| enabled)
+            .map(|(&name, _)| name)
+            .collect()
+    }
+
+    /// The inverse of [`enabled_names`][Supports::enabled_names], returning a `ParamError` for
+    /// any name that isn't one of the field names.
+    ///
+    /// ```
+    /// use lead_oxide::proxy::Supports;
+    ///
+    /// let supports = Supports::from_names(&["https", "post"]).unwrap();
+    /// assert_eq!(supports, Supports { https: true, post: true, ..Supports::default() });
+    /// assert!(Supports::from_names(&["teleport"]).is_err());
+    /// ```
+    pub fn from_names(names: &[&str]) -> Result<Self, ParamError<String>> {
+        let mut supports = Self::default();
+        for &name in names {
+            let field = match name {
+                "https" => &mut supports.https,
+                "get" => &mut supports.get,
+                "post" => &mut supports.post,
+                "cookies" => &mut supports.cookies,
+                "referer" => &mut supports.referer,
+                "forwards_user_agent" => &mut supports.forwards_user_agent,
+                "connects_to_google" => &mut supports.connects_to_google,
+                unknown => return Err(ParamError::unrecognized(unknown.to_string())),
+            };
+            *field = true;
+        }
+
+        Ok(supports)
+    }
+
+    /// If every attribute that `query` requires is supported. Attributes that aren't required can
+    /// be either.
+    ///
+    /// ```
+    /// use lead_oxide::proxy::{Supports, SupportsQuery};
+    ///
+    /// let supports = Supports { post: true, cookies: true, ..Supports::default() };
+    /// assert!(supports.satisfies(&SupportsQuery::new().post(true).cookies(true)));
+    /// assert!(!supports.satisfies(&SupportsQuery::new().post(true).https(true)));
+    /// ```
+    pub fn satisfies(&self, query: &SupportsQuery) -> bool {
+        query
+            .required
+            .fields()
+            .iter()
+            .zip(self.fields().iter())
+            .all(|(&required, &supported)| !required || supported)
+    }
+}
+
+/// The attributes required by [`Supports::satisfies`][Supports::satisfies], where no attributes are
+/// required by default.
+///
+/// This is useful for filtering fetched proxies on more attributes than the API can filter on at
+/// once, or for reusing the same requirements across different lists of proxies.
+///
+/// ```
+/// use lead_oxide::proxy::{Proxy, SupportsQuery};
+///
+/// fn keep_capable(proxies: &mut Vec<Proxy>) {
+///     let query = SupportsQuery::new().post(true).cookies(true);
+///     proxies.retain(|proxy| proxy.supports.satisfies(&query));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SupportsQuery {
+    required: Supports,
+}
+
+impl SupportsQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// If the proxy has to support https.
+    pub fn https(mut self, required: bool) -> Self {
+        self.required.https = required;
+        self
+    }
+
+    /// If the proxy has to support get requests.
+    pub fn get(mut self, required: bool) -> Self {
+        self.required.get = required;
+        self
+    }
+
+    /// If the proxy has to support post requests.
+    pub fn post(mut self, required: bool) -> Self {
+        self.required.post = required;
+        self
+    }
+
+    /// If the proxy has to support cookies.
+    pub fn cookies(mut self, required: bool) -> Self {
+        self.required.cookies = required;
+        self
+    }
+
+    /// If the proxy has to support referer requests.
+    pub fn referer(mut self, required: bool) -> Self {
+        self.required.referer = required;
+        self
+    }
+
+    /// If the proxy has to forward your user agent.
+    pub fn forwards_user_agent(mut self, required: bool) -> Self {
+        self.required.forwards_user_agent = required;
+        self
+    }
+
+    /// If the proxy has to be able to connect to google.
+    pub fn connects_to_google(mut self, required: bool) -> Self {
+        self.required.connects_to_google = required;
+        self
+    }
+}
+
 impl From<RawSupports> for Supports {
     fn from(raw: RawSupports) -> Self {
         let parse_field = |field| match field {
@@ -144,8 +1087,243 @@ impl From<RawSupports> for Supports {
             post: parse_field(raw.post),
             cookies: parse_field(raw.cookies),
             referer: parse_field(raw.referer),
-            forwards_user_agent: parse_field(raw.forwards_user_agent),
-            connects_to_google: parse_field(raw.connects_to_google),
+            forwards_user_agent: parse_field(raw.user_agent),
+            connects_to_google: parse_field(raw.google),
+        }
+    }
+}
+
+/// Endlessly cycles through a fixed list of proxies.
+///
+/// This is handy for spreading load over a set of proxies that was fetched once. Nothing is
+/// tracked about how the proxies are doing, each call to [`next`][RoundRobin::next] just moves on
+/// to the following proxy, wrapping around at the end.
+///
+/// ```no_run
+/// use lead_oxide::{fetcher::Fetcher, proxy::RoundRobin};
+///
+/// let mut fetcher = Fetcher::default();
+/// let mut proxies = RoundRobin::new(fetcher.try_get(5).unwrap()).unwrap();
+/// for _ in 0..20 {
+///     println!("Using {}", proxies.next().socket);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundRobin {
+    proxies: Vec<Proxy>,
+    current: usize,
+}
+
+impl RoundRobin {
+    /// Creates a cycler over `proxies`, returning `None` if there are no proxies to cycle through.
+    pub fn new(proxies: Vec<Proxy>) -> Option<Self> {
+        if proxies.is_empty() {
+            None
+        } else {
+            Some(Self {
+                proxies,
+                current: 0,
+            })
+        }
+    }
+
+    /// Returns the next proxy, starting over after the last one.
+    // Can't be an `Iterator` since the returned proxy borrows from `self`
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> &Proxy {
+        let proxy = &self.proxies[self.current];
+        self.current = (self.current + 1) % self.proxies.len();
+        proxy
+    }
+
+    /// The proxies being cycled through.
+    pub fn proxies(&self) -> &[Proxy] {
+        &self.proxies
+    }
+
+    /// Consumes the cycler returning the underlying proxies.
+    pub fn into_inner(self) -> Vec<Proxy> {
+        self.proxies
+    }
+}
+
+// Orderings only compare their key so equality has to as well to stay consistent with `Ord`
+macro_rules! proxy_ordering {
+    ($(#[$meta:meta])* $name:ident, |$proxy:ident| $key:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug)]
+        pub struct $name(pub Proxy);
+
+        impl $name {
+            fn key(&self) -> impl Ord {
+                let $proxy = &self.0;
+                $key
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.key().cmp(&other.key())
+            }
+        }
+
+        impl From<Proxy> for $name {
+            fn from(proxy: Proxy) -> Self {
+                Self(proxy)
+            }
+        }
+    };
+}
+
+proxy_ordering!(
+    /// Orders proxies by how quickly they connect where faster proxies are greater, so a
+    /// `BinaryHeap<BySpeed>` always pops the fastest proxy.
+    ///
+    /// ```no_run
+    /// use lead_oxide::{fetcher::Fetcher, proxy::BySpeed};
+    /// use std::collections::BinaryHeap;
+    ///
+    /// let mut fetcher = Fetcher::default();
+    /// let mut heap: BinaryHeap<_> = fetcher.try_get(5).unwrap().into_iter().map(BySpeed).collect();
+    /// let BySpeed(fastest) = heap.pop().unwrap();
+    /// ```
+    BySpeed,
+    |proxy| Reverse(proxy.time_to_connect)
+);
+
+proxy_ordering!(
+    /// Orders proxies by `last_checked` where more recently checked proxies are greater, so a
+    /// `BinaryHeap<ByFreshness>` always pops the freshest proxy.
+    ByFreshness,
+    |proxy| proxy.last_checked
+);
+
+proxy_ordering!(
+    /// Orders proxies by quality where greater is better, so a `BinaryHeap<ByQuality>` always
+    /// pops the best proxy. Quality is judged by the anonymity level first (elite, then
+    /// anonymous, then transparent), then by the number of [`Supports`][Supports] attributes, and
+    /// finally by speed.
+    ByQuality,
+    |proxy| {
+        let level = match proxy.level {
+            Level::Elite => 2,
+            Level::Anonymous => 1,
+            Level::Transparent => 0,
+        };
+        let supported = proxy.supports.fields().iter().filter(|&&field| field).count();
+
+        (level, supported, Reverse(proxy.time_to_connect))
+    }
+);
+
+/// Identifies a proxy by its `socket` alone, for use as a `HashMap` or `HashSet` key.
+///
+/// Two [`Proxy`][Proxy] values are only equal when every field matches, so the same proxy
+/// returned by the API at different times (like with a newer `last_checked`) counts as a different
+/// proxy. `ProxyId` considers them the same, which makes it the right key for tracking proxies
+/// you've already used. This is the same equality that [`merge_freshest`][merge_freshest] and
+/// the `Fetcher`'s deduping use.
+///
+/// ```
+/// use lead_oxide::proxy::{Proxy, ProxyId};
+/// use std::collections::HashSet;
+///
+/// fn first_use(used: &mut HashSet<ProxyId>, proxy: &Proxy) -> bool {
+///     used.insert(ProxyId::from(proxy))
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProxyId(pub SocketAddr);
+
+impl From<&Proxy> for ProxyId {
+    fn from(proxy: &Proxy) -> Self {
+        Self(proxy.socket)
+    }
+}
+
+impl fmt::Display for ProxyId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Internal
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    /// A proxy for tests to tweak, where each `with_*` method overrides the one field the test
+    /// cares about.
+    pub(crate) fn proxy() -> Proxy {
+        Proxy {
+            socket: "1.2.3.4:1234".parse().unwrap(),
+            country: Country::US,
+            last_checked: NaiveDate::from_ymd_opt(2020, 12, 13)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            level: Level::Elite,
+            protocol: Protocol::Http,
+            time_to_connect: Duration::from_secs(1),
+            supports: Supports::default(),
+        }
+    }
+
+    impl Proxy {
+        pub(crate) fn with_socket(mut self, socket: &str) -> Self {
+            self.socket = socket.parse().unwrap();
+            self
+        }
+
+        pub(crate) fn with_port(mut self, port: u16) -> Self {
+            self.socket.set_port(port);
+            self
+        }
+
+        pub(crate) fn with_country(mut self, country: Country) -> Self {
+            self.country = country;
+            self
+        }
+
+        pub(crate) fn with_last_checked(mut self, last_checked: NaiveDateTime) -> Self {
+            self.last_checked = last_checked;
+            self
+        }
+
+        pub(crate) fn with_level(mut self, level: Level) -> Self {
+            self.level = level;
+            self
+        }
+
+        pub(crate) fn with_protocol(mut self, protocol: Protocol) -> Self {
+            self.protocol = protocol;
+            self
+        }
+
+        pub(crate) fn with_time_to_connect(mut self, time_to_connect: Duration) -> Self {
+            self.time_to_connect = time_to_connect;
+            self
+        }
+
+        pub(crate) fn with_supports(mut self, supports: Supports) -> Self {
+            self.supports = supports;
+            self
         }
     }
 }
@@ -153,6 +1331,8 @@ impl From<RawSupports> for Supports {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::types::Countries;
+    use test_util::proxy;
 
     use chrono::NaiveDate;
     use std::{fs, path::Path};
@@ -164,7 +1344,7 @@ mod test {
         let raw_response = fs::read_to_string(&sample_file).expect("Can't open the response file");
 
         // And now onto testing
-        let proxies = proxies_from_json(&raw_response)?;
+        let proxies = proxies_from_json(&raw_response, false)?;
 
         let date = NaiveDate::from_ymd_opt(2020, 12, 13).unwrap();
 
@@ -227,6 +1407,838 @@ mod test {
         }
         assert_eq!(proxies, ideal);
 
+        // Unless we keep the unknown countries
+        let proxies = proxies_from_json(&raw_response, true)?;
+        assert_eq!(proxies.len(), ideal.len() + 2);
+        let unknown: Vec<_> = proxies
+            .iter()
+            .filter(|proxy| proxy.country == Country::Unspecified)
+            .map(|proxy| proxy.socket)
+            .collect();
+        assert_eq!(
+            unknown,
+            [
+                "45.236.172.146:999".parse().unwrap(),
+                "188.226.141.211:3128".parse().unwrap()
+            ]
+        );
+
+        // IPv6 sockets work when they're bracketed
+        let ipv6 = raw_response.replacen("67.225.164.154:80", "[2001:db8::1]:80", 1);
+        let proxies = proxies_from_json(&ipv6, false)?;
+        assert_eq!(proxies[0].socket, "[2001:db8::1]:80".parse().unwrap());
+        assert_eq!(proxies[1..], ideal[1..]);
+        // and are rejected otherwise
+        let unbracketed = raw_response.replacen("35.181.4.4:80", "2001:db8::2:80", 1);
+        let err = proxies_from_json(&unbracketed, false).unwrap_err();
+        assert!(err.to_string().contains("invalid socket: '2001:db8::2:80'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn streaming() -> Result<(), serde_json::Error> {
+        for sample in &["response.json", "unknown_countries.json"] {
+            let sample_file = Path::new("tests").join("samples").join(sample);
+            let raw_response =
+                fs::read_to_string(&sample_file).expect("Can't open the response file");
+
+            for &keep_unknown in &[false, true] {
+                let file = fs::File::open(&sample_file).expect("Can't open the response file");
+                assert_eq!(
+                    proxies_from_json_streaming(file, keep_unknown)?,
+                    proxies_from_json(&raw_response, keep_unknown)?
+                );
+            }
+        }
+
+        // Responses without any data are still an error
+        assert!(proxies_from_json_streaming(&br#"{"count": 0}"#[..], false).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_countries() -> Result<(), serde_json::Error> {
+        let sample_file = Path::new("tests")
+            .join("samples")
+            .join("unknown_countries.json");
+        let raw_response = fs::read_to_string(&sample_file).expect("Can't open the response file");
+
+        // Bogus country codes are dropped by default
+        let opts = Opts::default();
+        let proxies = proxies_from_json(&raw_response, opts.keep_unknown_countries)?;
+        assert_eq!(proxies.len(), 1);
+        assert_eq!(proxies[0].country, Country::US);
+
+        // but are kept when requested
+        let opts = Opts::builder().keep_unknown_countries(true).build();
+        let proxies = proxies_from_json(&raw_response, opts.keep_unknown_countries)?;
+        let countries: Vec<_> = proxies.iter().map(|proxy| proxy.country).collect();
+        assert_eq!(
+            countries,
+            [
+                Country::US,
+                Country::Unspecified,
+                Country::Unspecified,
+                Country::Unspecified
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_deserialization() -> Result<(), serde_json::Error> {
+        let sample_file = Path::new("tests").join("samples").join("response.json");
+        let raw_response = fs::read_to_string(&sample_file).expect("Can't open the response file");
+
+        // Nothing gets filtered or transformed
+        let raw_proxies = raw_proxies_from_json(&raw_response)?;
+        assert_eq!(raw_proxies.len(), 6);
+        assert_eq!(
+            raw_proxies[2],
+            RawProxy {
+                ip_port: "45.236.172.146:999".to_string(),
+                ip: "45.236.172.146".to_string(),
+                port: "999".to_string(),
+                country: String::new(),
+                last_checked: "2020-12-13 20:01:52".to_string(),
+                proxy_level: "anonymous".to_string(),
+                r#type: "http".to_string(),
+                speed: "12".to_string(),
+                support: RawSupports {
+                    https: Some(0),
+                    get: Some(1),
+                    post: Some(1),
+                    cookies: Some(1),
+                    referer: Some(1),
+                    user_agent: Some(1),
+                    google: Some(0),
+                },
+                extra: HashMap::new(),
+            }
+        );
+
+        // And unknown fields are kept too
+        let with_extra = raw_response.replacen(
+            r#""ip": "67.225.164.154","#,
+            r#""ip": "67.225.164.154", "asn": 15169,"#,
+            1,
+        );
+        let raw_proxies = raw_proxies_from_json(&with_extra)?;
+        assert_eq!(raw_proxies[0].extra.get("asn"), Some(&15169.into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn transparent_level() -> Result<(), serde_json::Error> {
+        let sample_file = Path::new("tests").join("samples").join("response.json");
+        let raw_response = fs::read_to_string(&sample_file).expect("Can't open the response file");
+
+        // Unfiltered responses can include transparent proxies even though it's not a filter
+        let transparent = raw_response.replacen(
+            r#""proxy_level": "elite""#,
+            r#""proxy_level": "transparent""#,
+            1,
+        );
+        let original = proxies_from_json(&raw_response, false)?;
+        let proxies = proxies_from_json(&transparent, false)?;
+        assert_eq!(proxies.len(), original.len());
+        assert_eq!(proxies[0].level, Level::Transparent);
+        assert_eq!(proxies[1..], original[1..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn connect() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = listener.local_addr().unwrap();
+        let proxy = Proxy { socket, ..proxy() };
+
+        let stream = proxy.connect(Duration::from_secs(1)).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), socket);
+    }
+
+    #[cfg(feature = "check")]
+    #[test]
+    fn check() {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+            thread,
+        };
+
+        // Acts as both the proxy and the target
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 1024];
+                let len = stream.read(&mut buf).unwrap();
+                // The connection that's only for timing the connect
+                if len == 0 {
+                    continue;
+                }
+
+                let connect = String::from_utf8_lossy(&buf[..len]).into_owned();
+                stream
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .unwrap();
+                let _ = stream.read(&mut buf).unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                    .unwrap();
+                return connect;
+            }
+
+            unreachable!("The listener never stops");
+        });
+
+        let proxy = Proxy { socket, ..proxy() };
+        let report = proxy
+            .check("http://example.com/", Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(report.status, 204);
+        assert!(report.ok());
+        assert!(server.join().unwrap().starts_with("CONNECT example.com:80"));
+
+        // Nothing to connect to anymore
+        assert!(matches!(
+            proxy.check("http://example.com/", Duration::from_secs(1)),
+            Err(CheckError::Connect { .. })
+        ));
+
+        let socks = proxy.with_protocol(Protocol::Socks4);
+        assert!(matches!(
+            socks.check("http://example.com/", Duration::from_secs(1)),
+            Err(CheckError::Unsupported { .. })
+        ));
+    }
+
+    #[cfg(feature = "check")]
+    #[test]
+    fn check_socks5() {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+            thread,
+        };
+
+        // A SOCKS5 proxy without authentication that also acts as the target
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 1024];
+                let len = stream.read(&mut buf).unwrap();
+                // The connection that's only for timing the connect
+                if len == 0 {
+                    continue;
+                }
+
+                // Offers the methods it supports, so pick no authentication. `ureq` waits on the
+                // handshake from another thread and can miss it finishing right away, so hold off
+                assert_eq!(buf[0], 5, "Not a SOCKS5 greeting");
+                thread::sleep(Duration::from_millis(50));
+                stream.write_all(&[5, 0]).unwrap();
+                let len = stream.read(&mut buf).unwrap();
+                let connect = buf[..len].to_vec();
+                stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+
+                let _ = stream.read(&mut buf).unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                    .unwrap();
+                return connect;
+            }
+
+            unreachable!("The listener never stops");
+        });
+
+        let proxy = proxy().with_protocol(Protocol::Socks5);
+        let proxy = Proxy { socket, ..proxy };
+        let report = proxy
+            .check("http://example.com/", Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(report.status, 204);
+        // A connect command for the target's domain and port
+        let connect = server.join().unwrap();
+        assert_eq!(connect[..4], [5, 1, 0, 3]);
+        let domain = &connect[5..connect.len() - 2];
+        assert_eq!(domain, b"example.com");
+        assert_eq!(connect[connect.len() - 2..], 80u16.to_be_bytes());
+    }
+
+    #[cfg(feature = "check")]
+    #[test]
+    fn filter_working() {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+            thread,
+        };
+
+        // A proxy that tunnels every request to a target responding with `status`
+        fn mock_proxy(status: u16) -> SocketAddr {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let socket = listener.local_addr().unwrap();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut buf = [0; 1024];
+                    if stream.read(&mut buf).unwrap() == 0 {
+                        continue;
+                    }
+
+                    let _ = stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n");
+                    let _ = stream.read(&mut buf);
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 {} Mocked\r\nContent-Length: 0\r\n\r\n",
+                        status
+                    );
+                }
+            });
+
+            socket
+        }
+
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let sockets = [
+            mock_proxy(200),
+            closed,
+            mock_proxy(503),
+            mock_proxy(204),
+            mock_proxy(200),
+        ];
+        let proxies: Vec<_> = sockets
+            .iter()
+            .map(|&socket| Proxy { socket, ..proxy() })
+            .collect();
+
+        for &concurrency in &[0, 1, 2, 10] {
+            let working = super::filter_working(
+                proxies.clone(),
+                "http://example.com/",
+                concurrency,
+                Duration::from_secs(1),
+            );
+            let working: Vec<_> = working.iter().map(|proxy| proxy.socket).collect();
+            assert_eq!(working, [sockets[0], sockets[3], sockets[4]]);
+        }
+        assert!(super::filter_working(
+            Vec::new(),
+            "http://example.com/",
+            4,
+            Duration::from_secs(1)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn round_robin() {
+        assert_eq!(RoundRobin::new(Vec::new()), None);
+
+        let proxies: Vec<_> = (1..=3).map(|port| proxy().with_port(port)).collect();
+
+        let mut round_robin = RoundRobin::new(proxies.clone()).unwrap();
+        let ports: Vec<_> = (0..7).map(|_| round_robin.next().socket.port()).collect();
+        assert_eq!(ports, [1, 2, 3, 1, 2, 3, 1]);
+        assert_eq!(round_robin.into_inner(), proxies);
+    }
+
+    #[test]
+    fn merging_freshest() {
+        let date = NaiveDate::from_ymd_opt(2020, 12, 13).unwrap();
+        let proxy = |port: u16, hour: u32| {
+            proxy()
+                .with_port(port)
+                .with_last_checked(date.and_hms_opt(hour, 0, 0).unwrap())
+        };
+
+        let merged = merge_freshest(vec![
+            proxy(1, 2),
+            proxy(2, 5),
+            proxy(1, 4),
+            proxy(3, 1),
+            proxy(2, 3),
+        ]);
+        assert_eq!(merged, [proxy(1, 4), proxy(2, 5), proxy(3, 1)]);
+    }
+
+    #[test]
+    fn grouping_by_country() {
+        let proxy = |port: u16, country: Country| proxy().with_port(port).with_country(country);
+
+        let groups = group_by_country(vec![
+            proxy(1, Country::US),
+            proxy(2, Country::CA),
+            proxy(3, Country::Unspecified),
+            proxy(4, Country::US),
+            proxy(5, Country::US),
+        ]);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(
+            groups[&Country::US],
+            [
+                proxy(1, Country::US),
+                proxy(4, Country::US),
+                proxy(5, Country::US)
+            ]
+        );
+        assert_eq!(groups[&Country::CA], [proxy(2, Country::CA)]);
+        assert_eq!(
+            groups[&Country::Unspecified],
+            [proxy(3, Country::Unspecified)]
+        );
+
+        assert!(group_by_country(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn orderings() {
+        use std::collections::BinaryHeap;
+
+        let base = proxy()
+            .with_level(Level::Anonymous)
+            .with_time_to_connect(Duration::from_secs(5));
+        let fast = base.clone().with_time_to_connect(Duration::from_secs(1));
+        let fresh = base
+            .clone()
+            .with_last_checked(base.last_checked + chrono::Duration::hours(1));
+        let elite = base.clone().with_level(Level::Elite);
+        let all = [base.clone(), fast.clone(), fresh.clone(), elite.clone()];
+
+        let mut by_speed: BinaryHeap<_> = all.iter().cloned().map(BySpeed).collect();
+        assert_eq!(by_speed.pop().unwrap().0, fast);
+        let mut by_freshness: BinaryHeap<_> = all.iter().cloned().map(ByFreshness).collect();
+        assert_eq!(by_freshness.pop().unwrap().0, fresh);
+        let mut by_quality: BinaryHeap<_> = all.iter().cloned().map(ByQuality).collect();
+        assert_eq!(by_quality.pop().unwrap().0, elite);
+        // Speed breaks ties in quality
+        assert_eq!(by_quality.pop().unwrap().0, fast);
+
+        // Equality only considers the ordering's key
+        assert_eq!(BySpeed(base.clone()), BySpeed(fresh.clone()));
+        assert_ne!(ByFreshness(base), ByFreshness(fresh));
+    }
+
+    #[test]
+    fn to_url() {
+        let proxy = |protocol| proxy().with_port(1080).with_protocol(protocol);
+
+        assert_eq!(proxy(Protocol::Http).to_url(), "http://1.2.3.4:1080");
+        assert_eq!(proxy(Protocol::Socks4).to_url(), "socks4://1.2.3.4:1080");
+        assert_eq!(proxy(Protocol::Socks5).to_url(), "socks5://1.2.3.4:1080");
+
+        let ipv6 = proxy(Protocol::Http).with_socket("[2001:db8::1]:8080");
+        assert_eq!(ipv6.to_url(), "http://[2001:db8::1]:8080");
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn reqwest_conversion() {
+        // `reqwest::Proxy` doesn't expose what it proxies, but its `Debug` output does
+        let converted = |proxy: &Proxy| format!("{:?}", reqwest::Proxy::try_from(proxy).unwrap());
+        let mut proxy = proxy().with_port(1080);
+
+        // Plain HTTP proxies are only used for HTTP traffic
+        let http = converted(&proxy);
+        assert!(http.starts_with("Proxy(Http("), "{}", http);
+        assert!(http.contains(r#"scheme: "http""#), "{}", http);
+        // unless they support HTTPS
+        proxy.supports.https = true;
+        let all = converted(&proxy);
+        assert!(all.starts_with("Proxy(All("), "{}", all);
+        assert!(all.contains(r#"scheme: "http""#), "{}", all);
+        // while SOCKS proxies are used for everything
+        proxy.supports.https = false;
+        proxy.protocol = Protocol::Socks5;
+        let socks = converted(&proxy);
+        assert!(socks.starts_with("Proxy(All("), "{}", socks);
+        assert!(socks.contains(r#"scheme: "socks5""#), "{}", socks);
+        assert!(socks.contains("port: Some(1080)"), "{}", socks);
+    }
+
+    #[test]
+    fn serialization() -> Result<(), serde_json::Error> {
+        let mut proxy = proxy()
+            .with_port(1080)
+            .with_country(Country::DE)
+            .with_last_checked(
+                NaiveDate::from_ymd_opt(2020, 12, 13)
+                    .unwrap()
+                    .and_hms_opt(1, 23, 45)
+                    .unwrap(),
+            )
+            .with_level(Level::Anonymous)
+            .with_protocol(Protocol::Socks5)
+            .with_time_to_connect(Duration::from_secs(7))
+            .with_supports(Supports {
+                https: true,
+                cookies: true,
+                ..Supports::default()
+            });
+
+        let json = serde_json::to_value(&proxy)?;
+        assert_eq!(json["socket"], "1.2.3.4:1080");
+        assert_eq!(json["country"], "DE");
+        assert_eq!(json["last_checked"], "2020-12-13 01:23:45");
+        assert_eq!(json["level"], "anonymous");
+        assert_eq!(json["protocol"], "socks5");
+        assert_eq!(json["time_to_connect"], 7);
+        assert_eq!(json["supports"]["https"], true);
+        assert_eq!(json["supports"]["get"], false);
+
+        let round_tripped: Proxy = serde_json::from_str(&serde_json::to_string(&proxy)?)?;
+        assert_eq!(round_tripped, proxy);
+
+        proxy.level = Level::Transparent;
+        let round_tripped: Proxy = serde_json::from_str(&serde_json::to_string(&proxy)?)?;
+        assert_eq!(round_tripped, proxy);
+
+        Ok(())
+    }
+
+    #[test]
+    fn socket_parsing() {
+        let v4 = "1.2.3.4:8080".parse().unwrap();
+        let v6 = "[2001:db8::1]:8080".parse().unwrap();
+
+        assert_eq!(parse_socket("1.2.3.4:8080"), Ok(v4));
+        assert_eq!(parse_socket("[2001:db8::1]:8080"), Ok(v6));
+        assert!(parse_socket("2001:db8::1:8080").is_err());
+        assert!(parse_socket("1.2.3.4").is_err());
+        assert!(parse_socket("[2001:db8::1]").is_err());
+        assert!(parse_socket("not a socket:80").is_err());
+    }
+
+    #[test]
+    fn txt_parsing() {
+        let proxies = proxies_from_txt("1.2.3.4:8080\r\n\n 5.6.7.8:1080 \n").unwrap();
+        let sockets: Vec<_> = proxies.iter().map(|proxy| proxy.socket).collect();
+        assert_eq!(
+            sockets,
+            [
+                "1.2.3.4:8080".parse().unwrap(),
+                "5.6.7.8:1080".parse().unwrap()
+            ]
+        );
+        // Metadata isn't included so it's all defaults
+        assert!(proxies
+            .iter()
+            .all(|proxy| proxy.country == Country::Unspecified
+                && proxy.level == Level::Transparent
+                && proxy.supports == Supports::default()));
+
+        // Error messages from the API aren't sockets
+        assert!(proxies_from_txt("No proxy").is_err());
+        assert!(proxies_from_txt("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn lightweight() {
+        // The protocol comes from the scheme when there is one
+        let proxies = proxies_from_txt("socks4://1.2.3.4:8080\n5.6.7.8:1080").unwrap();
+        let protocols: Vec<_> = proxies.iter().map(|proxy| proxy.protocol).collect();
+        assert_eq!(protocols, [Protocol::Socks4, Protocol::Http]);
+        assert_eq!(proxies[0].socket, "1.2.3.4:8080".parse().unwrap());
+        assert!(proxies_from_txt("gopher://1.2.3.4:8080").is_err());
+
+        let builder = Opts::builder()
+            .countries(Countries::allow().country(Country::US))
+            .max_connect_client_side(Duration::from_secs(5))
+            .post(true);
+        let opts = builder.clone().lightweight(true).build();
+        assert_eq!(opts, builder.clone().format_txt(true).build());
+
+        // The defaulted country and time to connect don't count against the proxy
+        let mut proxy = proxies.into_iter().next().unwrap();
+        proxy.supports.post = true;
+        assert!(proxy.satisfies(&opts));
+        assert!(!proxy.satisfies(&builder.build()));
+        // while everything that's known is still checked
+        proxy.supports.post = false;
+        assert!(!proxy.satisfies(&opts));
+    }
+
+    #[test]
+    fn display() {
+        let mut proxy = proxy()
+            .with_socket("10.0.0.1:1080")
+            .with_country(Country::DE)
+            .with_level(Level::Anonymous)
+            .with_time_to_connect(Duration::from_secs(12));
+        assert_eq!(
+            proxy.to_string(),
+            "10.0.0.1:1080 (http, anonymous, DE, 12s)"
+        );
+
+        proxy.country = Country::Unspecified;
+        proxy.level = Level::Transparent;
+        proxy.protocol = Protocol::Socks4;
+        proxy.time_to_connect = Duration::from_secs(0);
+        assert_eq!(
+            proxy.to_string(),
+            "10.0.0.1:1080 (socks4, transparent, ??, 0s)"
+        );
+    }
+
+    #[test]
+    fn supports_names() {
+        let all: Vec<_> = Supports::NAMES.to_vec();
+        let everything = Supports::from_names(&all).unwrap();
+        assert_eq!(everything.enabled_names(), all);
+        assert!(Supports::default().enabled_names().is_empty());
+
+        let some = Supports {
+            get: true,
+            referer: true,
+            connects_to_google: true,
+            ..Supports::default()
+        };
+        let names = some.enabled_names();
+        assert_eq!(names, ["get", "referer", "connects_to_google"]);
+        assert_eq!(Supports::from_names(&names), Ok(some));
+
+        assert_eq!(
+            Supports::from_names(&["post", "google"]),
+            Err(ParamError::unrecognized("google".to_string()))
+        );
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn csv() {
+        let proxies = [
+            proxy()
+                .with_last_checked(
+                    NaiveDate::from_ymd_opt(2020, 12, 13)
+                        .unwrap()
+                        .and_hms_opt(1, 2, 3)
+                        .unwrap(),
+                )
+                .with_protocol(Protocol::Socks5)
+                .with_time_to_connect(Duration::from_secs(5))
+                .with_supports(Supports {
+                    https: true,
+                    post: true,
+                    ..Supports::default()
+                }),
+            proxy()
+                .with_socket("[2001:db8::1]:8080")
+                .with_country(Country::Unspecified)
+                .with_last_checked(
+                    NaiveDate::from_ymd_opt(2021, 1, 2)
+                        .unwrap()
+                        .and_hms_opt(23, 59, 59)
+                        .unwrap(),
+                )
+                .with_level(Level::Transparent)
+                .with_time_to_connect(Duration::from_millis(1500)),
+        ];
+
+        let mut out = Vec::new();
+        to_csv(&proxies, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "ip,port,protocol,level,country,last_checked,seconds_to_connect,https,get,post,\
+             cookies,referer,forwards_user_agent,connects_to_google\n\
+             1.2.3.4,1234,socks5,elite,US,2020-12-13 01:02:03,5,true,false,true,false,false,\
+             false,false\n\
+             2001:db8::1,8080,http,transparent,,2021-01-02 23:59:59,1.5,false,false,false,false,\
+             false,false,false\n"
+        );
+
+        // Just the header without any proxies
+        let mut out = Vec::new();
+        to_csv(&[], &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn jsonl() -> Result<(), serde_json::Error> {
+        let proxy = proxy()
+            .with_last_checked(
+                NaiveDate::from_ymd_opt(2020, 12, 13)
+                    .unwrap()
+                    .and_hms_opt(1, 2, 3)
+                    .unwrap(),
+            )
+            .with_protocol(Protocol::Socks5)
+            .with_time_to_connect(Duration::from_secs(5))
+            .with_supports(Supports {
+                https: true,
+                ..Supports::default()
+            });
+        let ipv6 = proxy
+            .clone()
+            .with_socket("[2001:db8::1]:8080")
+            .with_country(Country::Unspecified);
+        let proxies = [proxy, ipv6];
+
+        let mut out = Vec::new();
+        write_jsonl(&proxies, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"socket":"1.2.3.4:1234","country":"US","last_checked":"2020-12-13 01:02:03","level":"elite","protocol":"socks5","time_to_connect":5,"supports":{"https":true,"get":false,"post":false,"cookies":false,"referer":false,"forwards_user_agent":false,"connects_to_google":false}}"#,
+                r#"{"socket":"[2001:db8::1]:8080","country":"","last_checked":"2020-12-13 01:02:03","level":"elite","protocol":"socks5","time_to_connect":5,"supports":{"https":true,"get":false,"post":false,"cookies":false,"referer":false,"forwards_user_agent":false,"connects_to_google":false}}"#,
+            ]
+        );
+
+        // Reading back gives the same proxies, skipping blank lines
+        let with_blanks = format!("\n{}\n\n", text);
+        assert_eq!(read_jsonl(with_blanks.as_bytes()).unwrap(), proxies);
+
+        let err = read_jsonl("{\"socket\": 1}\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        Ok(())
+    }
+
+    #[test]
+    fn staleness() {
+        let checked = NaiveDate::from_ymd_opt(2020, 12, 13)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let proxy = proxy().with_last_checked(checked);
+        let ten_mins = Duration::from_secs(10 * 60);
+
+        assert!(!proxy.is_stale(ten_mins, checked));
+        assert!(!proxy.is_stale(ten_mins, checked + chrono::Duration::minutes(10)));
+        assert!(proxy.is_stale(ten_mins, checked + chrono::Duration::seconds(10 * 60 + 1)));
+        assert!(proxy.is_stale(
+            Duration::from_secs(0),
+            checked + chrono::Duration::seconds(1)
+        ));
+        // Checked after `now`
+        assert!(!proxy.is_stale(Duration::from_secs(0), checked - chrono::Duration::hours(1)));
+
+        // It's been a while since 2020
+        assert!(proxy.is_stale_utc(ten_mins));
+    }
+
+    #[test]
+    fn proxy_id() {
+        use std::collections::HashSet;
+
+        let proxy = proxy().with_time_to_connect(Duration::from_secs(5));
+        let rechecked = proxy
+            .clone()
+            .with_last_checked(proxy.last_checked + chrono::Duration::hours(1))
+            .with_time_to_connect(Duration::from_secs(1));
+        let other_port = proxy.clone().with_port(4321);
+
+        // The same proxy checked at different times is still the same id
+        assert_ne!(proxy, rechecked);
+        assert_eq!(ProxyId::from(&proxy), ProxyId::from(&rechecked));
+        assert_ne!(ProxyId::from(&proxy), ProxyId::from(&other_port));
+
+        let mut used = HashSet::new();
+        assert!(used.insert(ProxyId::from(&proxy)));
+        assert!(!used.insert(ProxyId::from(&rechecked)));
+        assert!(used.insert(ProxyId::from(&other_port)));
+
+        assert_eq!(ProxyId::from(&proxy).to_string(), "1.2.3.4:1234");
+    }
+
+    #[test]
+    fn supports_query() {
+        let supports = Supports {
+            post: true,
+            cookies: true,
+            referer: true,
+            ..Supports::default()
+        };
+
+        // Nothing required is always satisfied
+        assert!(supports.satisfies(&SupportsQuery::new()));
+        assert!(Supports::default().satisfies(&SupportsQuery::new()));
+
+        // Only some of the supported attributes
+        assert!(supports.satisfies(&SupportsQuery::new().post(true)));
+        assert!(supports.satisfies(&SupportsQuery::new().post(true).cookies(true)));
+        // Partial matches aren't enough
+        assert!(!supports.satisfies(&SupportsQuery::new().post(true).https(true)));
+        assert!(!supports.satisfies(&SupportsQuery::new().connects_to_google(true)));
+        // Unsetting a requirement removes it
+        let query = SupportsQuery::new().https(true).post(true).https(false);
+        assert!(supports.satisfies(&query));
+
+        let all = SupportsQuery::new()
+            .https(true)
+            .get(true)
+            .post(true)
+            .cookies(true)
+            .referer(true)
+            .forwards_user_agent(true)
+            .connects_to_google(true);
+        assert!(!supports.satisfies(&all));
+        assert!(Supports::from_names(&Supports::NAMES)
+            .unwrap()
+            .satisfies(&all));
+    }
+
+    #[test]
+    fn last_checked_formats() {
+        let expected = NaiveDate::from_ymd_opt(2020, 12, 13)
+            .unwrap()
+            .and_hms_opt(20, 6, 41)
+            .unwrap();
+
+        assert_eq!(parse_last_checked("2020-12-13 20:06:41"), Some(expected));
+        assert_eq!(parse_last_checked("2020-12-13T20:06:41"), Some(expected));
+        assert_eq!(parse_last_checked("2020-12-13T20:06:41Z"), Some(expected));
+        // Offsets get normalized to UTC
+        assert_eq!(
+            parse_last_checked("2020-12-13T21:06:41+01:00"),
+            Some(expected)
+        );
+        assert_eq!(parse_last_checked("13/12/2020"), None);
+
+        // An invalid time only skips that proxy
+        let sample_file = Path::new("tests").join("samples").join("response.json");
+        let raw_response = fs::read_to_string(&sample_file).expect("Can't open the response file");
+        let bad_time = raw_response.replacen("2020-12-13 20:06:41", "yesterday", 1);
+        let original = proxies_from_json(&raw_response, false).unwrap();
+        assert_eq!(proxies_from_json(&bad_time, false).unwrap(), original[1..]);
+    }
+
+    #[test]
+    fn malformed_proxies() -> Result<(), serde_json::Error> {
+        let sample_file = Path::new("tests").join("samples").join("response.json");
+        let raw_response = fs::read_to_string(&sample_file).expect("Can't open the response file");
+
+        // A bad row gets skipped instead of panicking or failing the whole response
+        let original = proxies_from_json(&raw_response, false)?;
+        let bad_speed = raw_response.replacen(r#""speed": "10""#, r#""speed": "notanumber""#, 1);
+        assert_eq!(proxies_from_json(&bad_speed, false)?, original[1..]);
+
+        // Converting a raw proxy says what was wrong with it
+        let mut raw_proxies = raw_proxies_from_json(&bad_speed)?;
+        assert_eq!(
+            Proxy::try_from(raw_proxies.remove(0)),
+            Err(ProxyParseError::new("speed", "notanumber"))
+        );
+        assert_eq!(
+            Proxy::try_from(raw_proxies.remove(0)),
+            Ok(original[1].clone())
+        );
+
         Ok(())
     }
 }
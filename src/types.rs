@@ -6,7 +6,7 @@
 
 use crate::errors::ParamError;
 
-use std::{convert::TryFrom, fmt, time::Duration};
+use std::{convert::TryFrom, fmt, str::FromStr, time::Duration};
 
 use iso_country::Country;
 use serde::{Deserialize, Serialize};
@@ -25,13 +25,13 @@ impl<T> BoundedVal<T>
 where
     T: fmt::Debug + PartialEq + PartialOrd,
 {
-    pub fn new(val: T, bounds: (T, T)) -> Result<Self, ParamError<T>> {
+    pub fn new(val: T, bounds: (T, T)) -> Option<Self> {
         debug_assert!(bounds.0 <= bounds.1);
 
         if val >= bounds.0 && val <= bounds.1 {
-            Ok(Self { val })
+            Some(Self { val })
         } else {
-            Err(ParamError::out_of_bounds(val, bounds))
+            None
         }
     }
 }
@@ -47,9 +47,27 @@ macro_rules! bounded_val {
         impl $name {
             pub const BOUNDS: ($type, $type) = $bounds;
 
-            pub fn new(val: $type) -> Result<Self, ParamError<$type>> {
-                let inner = BoundedVal::new(val, Self::BOUNDS)?;
-                Ok(Self { inner })
+            pub fn new(val: $type) -> Result<Self, ParamError<Self>> {
+                match BoundedVal::new(val, Self::BOUNDS) {
+                    Some(inner) => Ok(Self { inner }),
+                    None => {
+                        // Reported as `Self` so the error can use its `Display`
+                        let (min, max) = Self::BOUNDS;
+                        Err(ParamError::out_of_bounds(
+                            Self::new_unchecked(val),
+                            (Self::new_unchecked(min), Self::new_unchecked(max)),
+                        ))
+                    }
+                }
+            }
+
+            /// Skips checking that `val` is within [`BOUNDS`][Self::BOUNDS] so that this can be
+            /// used in `const` contexts. An out of bounds value isn't caught by the library and is
+            /// sent to the API as is.
+            pub const fn new_unchecked(val: $type) -> Self {
+                Self {
+                    inner: BoundedVal { val },
+                }
             }
 
             pub fn value(&self) -> $type {
@@ -58,7 +76,7 @@ macro_rules! bounded_val {
         }
 
         impl TryFrom<$type> for $name {
-            type Error = ParamError<$type>;
+            type Error = ParamError<Self>;
 
             fn try_from(val: $type) -> Result<Self, Self::Error> {
                 Self::new(val)
@@ -76,9 +94,132 @@ const TIME_TO_CONNECT_BOUNDS: (Duration, Duration) =
 bounded_val! {LastChecked, Duration, LAST_CHECKED_BOUNDS}
 bounded_val! {TimeToConnect, Duration, TIME_TO_CONNECT_BOUNDS}
 
-pub(crate) struct NaiveResponse {
-    pub(crate) status: u16,
-    pub(crate) text: String,
+// `PartialOrd` can't be used in a `const fn` so this compares the nanoseconds instead
+const fn within_bounds(val: Duration, (min, max): (Duration, Duration)) -> bool {
+    val.as_nanos() >= min.as_nanos() && val.as_nanos() <= max.as_nanos()
+}
+
+impl LastChecked {
+    /// Creates a `LastChecked` from a whole number of minutes, returning an error when `mins` is
+    /// out of [bounds][Self::BOUNDS].
+    ///
+    /// ```
+    /// use lead_oxide::types::LastChecked;
+    /// use std::time::Duration;
+    ///
+    /// assert_eq!(
+    ///     LastChecked::from_minutes(10).unwrap().value(),
+    ///     Duration::from_secs(60 * 10)
+    /// );
+    /// assert!(LastChecked::from_minutes(0).is_err());
+    /// ```
+    pub fn from_minutes(mins: u64) -> Result<Self, ParamError<Self>> {
+        Self::new(Duration::from_secs(mins.saturating_mul(60)))
+    }
+
+    /// The `const` version of [`from_minutes`][Self::from_minutes], which panics when `mins` is
+    /// out of bounds instead. This fails to compile when used to define a `const`.
+    ///
+    /// ```
+    /// use lead_oxide::types::LastChecked;
+    ///
+    /// const HALF_HOUR: LastChecked = LastChecked::from_minutes_const(30);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use lead_oxide::types::LastChecked;
+    ///
+    /// const TOO_OLD: LastChecked = LastChecked::from_minutes_const(61);
+    /// ```
+    pub const fn from_minutes_const(mins: u64) -> Self {
+        let val = Duration::from_secs(mins.saturating_mul(60));
+        if !within_bounds(val, Self::BOUNDS) {
+            panic!("`LastChecked` must be from 1 to 60 minutes");
+        }
+
+        Self::new_unchecked(val)
+    }
+}
+
+impl TimeToConnect {
+    /// Creates a `TimeToConnect` from a whole number of seconds, returning an error when `secs`
+    /// is out of [bounds][Self::BOUNDS].
+    ///
+    /// ```
+    /// use lead_oxide::types::TimeToConnect;
+    /// use std::time::Duration;
+    ///
+    /// assert_eq!(
+    ///     TimeToConnect::from_secs(15).unwrap().value(),
+    ///     Duration::from_secs(15)
+    /// );
+    /// assert!(TimeToConnect::from_secs(61).is_err());
+    /// ```
+    pub fn from_secs(secs: u64) -> Result<Self, ParamError<Self>> {
+        Self::new(Duration::from_secs(secs))
+    }
+
+    /// The `const` version of [`from_secs`][Self::from_secs], which panics when `secs` is out of
+    /// bounds instead. This fails to compile when used to define a `const`.
+    ///
+    /// ```
+    /// use lead_oxide::types::TimeToConnect;
+    ///
+    /// const FAST: TimeToConnect = TimeToConnect::from_secs_const(5);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use lead_oxide::types::TimeToConnect;
+    ///
+    /// const INSTANT: TimeToConnect = TimeToConnect::from_secs_const(0);
+    /// ```
+    pub const fn from_secs_const(secs: u64) -> Self {
+        let val = Duration::from_secs(secs);
+        if !within_bounds(val, Self::BOUNDS) {
+            panic!("`TimeToConnect` must be from 1 to 60 seconds");
+        }
+
+        Self::new_unchecked(val)
+    }
+}
+
+fn fmt_amount(f: &mut fmt::Formatter, amount: f64, unit: &str) -> fmt::Result {
+    let plural = if amount == 1.0 { "" } else { "s" };
+    write!(f, "{} {}{}", amount, unit, plural)
+}
+
+/// Displays the value in minutes.
+///
+/// ```
+/// use lead_oxide::types::LastChecked;
+///
+/// assert_eq!(LastChecked::from_minutes_const(10).to_string(), "10 minutes");
+/// ```
+impl fmt::Display for LastChecked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_amount(f, self.value().as_secs_f64() / 60.0, "minute")
+    }
+}
+
+/// Displays the value in seconds.
+///
+/// ```
+/// use lead_oxide::types::TimeToConnect;
+///
+/// assert_eq!(TimeToConnect::from_secs_const(10).to_string(), "10 seconds");
+/// ```
+impl fmt::Display for TimeToConnect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_amount(f, self.value().as_secs_f64(), "second")
+    }
+}
+
+/// The status code and body of a response from the API, see
+/// [`Transport`][crate::fetcher::transport::Transport].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NaiveResponse {
+    pub status: u16,
+    pub text: String,
 }
 
 impl NaiveResponse {
@@ -117,6 +258,27 @@ impl Countries {
         Self::BlockList(String::new())
     }
 
+    /// Builds an allowlist from every country in `iter`. This panics on `Country::Unspecified` the
+    /// same as [`Countries::country`][Countries::country].
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::types::Countries;
+    ///
+    /// let user_input = ["US", "CA", "not a country"];
+    /// let countries = Countries::allow_from_iter(user_input.iter().filter_map(|tag| tag.parse().ok()));
+    /// assert_eq!(countries, Countries::allow().countries(&[Country::US, Country::CA]));
+    /// ```
+    pub fn allow_from_iter<I: IntoIterator<Item = Country>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::allow(), Self::country)
+    }
+
+    /// Builds a blocklist from every country in `iter`. This panics on `Country::Unspecified` the
+    /// same as [`Countries::country`][Countries::country].
+    pub fn block_from_iter<I: IntoIterator<Item = Country>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::block(), Self::country)
+    }
+
     pub fn is_empty(&self) -> bool {
         match self {
             Self::AllowList(countries) => countries.is_empty(),
@@ -132,12 +294,43 @@ impl Countries {
         self
     }
 
+    /// Adds `country` to the list. This panics on `Country::Unspecified`, use
+    /// [`Countries::try_country`][Countries::try_country] for untrusted input.
     pub fn country(self, country: Country) -> Self {
-        // TODO: make sure this is documented. Mention that unknowns are automatically filtered out
-        // if any country is used in the allow or blocklist
+        self.try_country(country).unwrap_or_else(|_| {
+            panic!("This library doesn't allow `Unspecified` country in the allow or blocklist")
+        })
+    }
+
+    /// Like [`Countries::countries`][Countries::countries], but returns an error on the first
+    /// `Country::Unspecified` instead of panicking.
+    pub fn try_countries(mut self, countries: &[Country]) -> Result<Self, ParamError<String>> {
+        for country in countries {
+            self = self.try_country(*country)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Adds `country` to the list, returning an error instead of panicking on
+    /// `Country::Unspecified`. Proxies with an unknown country never match an allow or blocklist
+    /// since they're dropped unless
+    /// [`keep_unknown_countries`][crate::opts::OptsBuilder::keep_unknown_countries] is set.
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::{errors::ParamError, types::Countries};
+    ///
+    /// assert!(Countries::allow().try_country(Country::US).is_ok());
+    /// assert_eq!(
+    ///     Countries::allow().try_country(Country::Unspecified),
+    ///     Err(ParamError::unsupported("Unspecified".to_string()))
+    /// );
+    /// ```
+    pub fn try_country(self, country: Country) -> Result<Self, ParamError<String>> {
+        // `Country`'s `Display` is empty for `Unspecified` so this uses the variant's name instead
         if let Country::Unspecified = country {
-            // TODO: this could be returned as a `ParamError` instead of panicking
-            panic!("This library doesn't allow `Unspecified` country in the allow or blocklist");
+            return Err(ParamError::unsupported(format!("{:?}", country)));
         }
 
         let push_country = |list: String, new_tag: Country| {
@@ -149,9 +342,144 @@ impl Countries {
             }
         };
 
-        match self {
+        Ok(match self {
             Self::AllowList(list) => Self::AllowList(push_country(list, country)),
             Self::BlockList(list) => Self::BlockList(push_country(list, country)),
+        })
+    }
+
+    /// Removes `country` from the list if it's present.
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::types::Countries;
+    ///
+    /// let countries = Countries::allow().country(Country::US).remove(Country::US);
+    /// assert!(countries.is_empty());
+    /// ```
+    pub fn remove(self, country: Country) -> Self {
+        let remove_country = |list: String| {
+            let tag = country.to_string();
+            list.split(',')
+                .filter(|existing| *existing != tag)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        match self {
+            Self::AllowList(list) => Self::AllowList(remove_country(list)),
+            Self::BlockList(list) => Self::BlockList(remove_country(list)),
+        }
+    }
+
+    /// Returns `true` if `country` is in the list.
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::types::Countries;
+    ///
+    /// let countries = Countries::block().country(Country::CN);
+    /// assert!(countries.contains(&Country::CN));
+    /// assert!(!countries.contains(&Country::US));
+    /// ```
+    pub fn contains(&self, country: &Country) -> bool {
+        let tag = country.to_string();
+        self.as_codes()
+            .split(',')
+            .any(|existing| !existing.is_empty() && existing == tag)
+    }
+
+    /// The number of countries in the list.
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::types::Countries;
+    ///
+    /// assert_eq!(Countries::allow().len(), 0);
+    /// assert_eq!(Countries::allow().countries(&[Country::US, Country::CA]).len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Iterates over the countries in the list in the order they were added.
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::types::Countries;
+    ///
+    /// let countries = Countries::block().countries(&[Country::CN, Country::RU]);
+    /// let listed: Vec<_> = countries.iter().collect();
+    /// assert_eq!(listed, [Country::CN, Country::RU]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = Country> + '_ {
+        // Every tag was added from a `Country` so they always parse back
+        self.as_codes()
+            .split(',')
+            .filter(|tag| !tag.is_empty())
+            .filter_map(|tag| tag.parse().ok())
+    }
+
+    /// Adds every country in a well-known [`CountryGroup`][CountryGroup] to the list.
+    ///
+    /// ```
+    /// use lead_oxide::types::{Countries, CountryGroup};
+    ///
+    /// // Steer clear of the Five Eyes
+    /// let countries = Countries::block().group(CountryGroup::FiveEyes);
+    /// ```
+    pub fn group(self, group: CountryGroup) -> Self {
+        self.countries(group.countries())
+    }
+
+    /// The comma separated country codes in the list, exactly as they're sent to the API.
+    ///
+    /// ```
+    /// use iso_country::Country;
+    /// use lead_oxide::types::Countries;
+    ///
+    /// let countries = Countries::allow().countries(&[Country::CH, Country::ES]);
+    /// assert_eq!(countries.as_codes(), "CH,ES");
+    /// ```
+    pub fn as_codes(&self) -> &str {
+        match self {
+            Self::AllowList(list) => list,
+            Self::BlockList(list) => list,
+        }
+    }
+
+    /// Whether this is an allowlist or a blocklist.
+    ///
+    /// ```
+    /// use lead_oxide::types::{Countries, ListKind};
+    ///
+    /// assert_eq!(Countries::block().kind(), ListKind::Block);
+    /// ```
+    pub fn kind(&self) -> ListKind {
+        match self {
+            Self::AllowList(_) => ListKind::Allow,
+            Self::BlockList(_) => ListKind::Block,
+        }
+    }
+
+    /// Internal
+    // Lists of the same kind get unioned while a different kind of list replaces the base
+    pub(crate) fn merge(&self, overrides: &Self) -> Self {
+        let union = |base: &str, other: &str| {
+            let mut union: Vec<_> = base.split(',').filter(|tag| !tag.is_empty()).collect();
+            for tag in other.split(',') {
+                if !union.contains(&tag) {
+                    union.push(tag);
+                }
+            }
+            union.join(",")
+        };
+
+        match (self, overrides) {
+            (base, overrides) if overrides.is_empty() => base.clone(),
+            (Self::AllowList(base), Self::AllowList(other)) => Self::AllowList(union(base, other)),
+            (Self::BlockList(base), Self::BlockList(other)) => Self::BlockList(union(base, other)),
+            (_, overrides) => overrides.clone(),
         }
     }
 }
@@ -163,11 +491,97 @@ impl Default for Countries {
     }
 }
 
+/// The kind of list that [`Countries`][Countries] is, see [`Countries::kind`][Countries::kind].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ListKind {
+    /// Only proxies from the listed countries are returned.
+    Allow,
+    /// Proxies from the listed countries are never returned.
+    Block,
+}
+
+/// Well-known political and economic groupings of countries for use with
+/// [`Countries::group`][Countries::group].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CountryGroup {
+    /// The Five Eyes intelligence alliance.
+    FiveEyes,
+    /// The Five Eyes along with Denmark, France, the Netherlands, and Norway.
+    NineEyes,
+    /// The Nine Eyes along with Belgium, Germany, Italy, Spain, and Sweden.
+    FourteenEyes,
+    /// The 27 member states of the European Union.
+    Eu27,
+    /// The member states of the Association of Southeast Asian Nations.
+    Asean,
+}
+
+impl CountryGroup {
+    /// The countries that make up the group.
+    pub fn countries(self) -> &'static [Country] {
+        use Country::*;
+
+        // The larger eyes groups just extend the smaller ones so they share a table
+        const FOURTEEN_EYES: &[Country] = &[
+            US, GB, CA, AU, NZ, // Five Eyes
+            DK, FR, NL, NO, // Nine Eyes
+            BE, DE, IT, ES, SE, // Fourteen Eyes
+        ];
+        const EU27: &[Country] = &[
+            AT, BE, BG, HR, CY, CZ, DK, EE, FI, FR, DE, GR, HU, IE, IT, LV, LT, LU, MT, NL, PL, PT,
+            RO, SK, SI, ES, SE,
+        ];
+        const ASEAN: &[Country] = &[BN, KH, ID, LA, MY, MM, PH, SG, TH, VN];
+
+        match self {
+            Self::FiveEyes => &FOURTEEN_EYES[..5],
+            Self::NineEyes => &FOURTEEN_EYES[..9],
+            Self::FourteenEyes => FOURTEEN_EYES,
+            Self::Eu27 => EU27,
+            Self::Asean => ASEAN,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Level {
     Anonymous,
     Elite,
+    /// Transparent proxies can show up in unfiltered responses, but the API doesn't allow
+    /// filtering on them so this is never sent as a parameter.
+    Transparent,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Anonymous => "anonymous",
+            Self::Elite => "elite",
+            Self::Transparent => "transparent",
+        })
+    }
+}
+
+/// Parses the same lowercase names used by the API.
+///
+/// ```
+/// use lead_oxide::types::Level;
+///
+/// assert_eq!("elite".parse(), Ok(Level::Elite));
+/// assert!("invisible".parse::<Level>().is_err());
+/// ```
+impl FromStr for Level {
+    type Err = ParamError<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "anonymous" => Ok(Self::Anonymous),
+            "elite" => Ok(Self::Elite),
+            "transparent" => Ok(Self::Transparent),
+            unknown => Err(ParamError::unrecognized(unknown.to_string())),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
@@ -178,6 +592,37 @@ pub enum Protocol {
     Socks5,
 }
 
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Http => "http",
+            Self::Socks4 => "socks4",
+            Self::Socks5 => "socks5",
+        })
+    }
+}
+
+/// Parses the same lowercase names used by the API.
+///
+/// ```
+/// use lead_oxide::types::Protocol;
+///
+/// assert_eq!("socks5".parse(), Ok(Protocol::Socks5));
+/// assert!("ftp".parse::<Protocol>().is_err());
+/// ```
+impl FromStr for Protocol {
+    type Err = ParamError<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http" => Ok(Self::Http),
+            "socks4" => Ok(Self::Socks4),
+            "socks5" => Ok(Self::Socks5),
+            unknown => Err(ParamError::unrecognized(unknown.to_string())),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,6 +632,18 @@ mod tests {
     mod bounded_vals {
         use super::*;
 
+        fn time_to_connect_err(val: Duration) -> ParamError<TimeToConnect> {
+            let (min, max) = TIME_TO_CONNECT_BOUNDS;
+            let unchecked = TimeToConnect::new_unchecked;
+            ParamError::out_of_bounds(unchecked(val), (unchecked(min), unchecked(max)))
+        }
+
+        fn last_checked_err(val: Duration) -> ParamError<LastChecked> {
+            let (min, max) = LAST_CHECKED_BOUNDS;
+            let unchecked = LastChecked::new_unchecked;
+            ParamError::out_of_bounds(unchecked(val), (unchecked(min), unchecked(max)))
+        }
+
         #[test]
         fn bounds_checking() {
             let zero_seconds = Duration::from_secs(0);
@@ -194,28 +651,92 @@ mod tests {
             let just_over_hour = Duration::from_secs(60 * 60 + 1);
 
             let bounds_err = TimeToConnect::try_from(zero_seconds).unwrap_err();
+            assert_eq!(bounds_err, time_to_connect_err(zero_seconds));
+
+            let bounds_err = TimeToConnect::try_from(just_over_minute).unwrap_err();
+            assert_eq!(bounds_err, time_to_connect_err(just_over_minute));
+
+            let bounds_err = LastChecked::try_from(zero_seconds).unwrap_err();
+            assert_eq!(bounds_err, last_checked_err(zero_seconds));
+
+            let bounds_err = LastChecked::try_from(just_over_hour).unwrap_err();
+            assert_eq!(bounds_err, last_checked_err(just_over_hour));
+        }
+
+        #[test]
+        fn display() {
+            assert_eq!(LastChecked::from_minutes_const(1).to_string(), "1 minute");
             assert_eq!(
-                bounds_err,
-                ParamError::out_of_bounds(zero_seconds, TIME_TO_CONNECT_BOUNDS)
+                LastChecked::from_minutes_const(10).to_string(),
+                "10 minutes"
             );
+            let ninety_secs = LastChecked::new(Duration::from_secs(90)).unwrap();
+            assert_eq!(ninety_secs.to_string(), "1.5 minutes");
+
+            assert_eq!(TimeToConnect::from_secs_const(1).to_string(), "1 second");
+            assert_eq!(TimeToConnect::from_secs_const(10).to_string(), "10 seconds");
+            let fractional = TimeToConnect::new(Duration::from_millis(2500)).unwrap();
+            assert_eq!(fractional.to_string(), "2.5 seconds");
+        }
+
+        #[test]
+        fn const_constructors() {
+            const HALF_HOUR: LastChecked = LastChecked::from_minutes_const(30);
+            const FAST: TimeToConnect = TimeToConnect::from_secs_const(5);
 
-            let bounds_err = TimeToConnect::try_from(just_over_minute).unwrap_err();
             assert_eq!(
-                bounds_err,
-                ParamError::out_of_bounds(just_over_minute, TIME_TO_CONNECT_BOUNDS)
+                HALF_HOUR,
+                LastChecked::new(Duration::from_secs(30 * 60)).unwrap()
+            );
+            assert_eq!(FAST, TimeToConnect::new(Duration::from_secs(5)).unwrap());
+            // The bounds themselves are allowed
+            assert_eq!(
+                LastChecked::from_minutes_const(1).value(),
+                LAST_CHECKED_BOUNDS.0
+            );
+            assert_eq!(
+                TimeToConnect::from_secs_const(60).value(),
+                TIME_TO_CONNECT_BOUNDS.1
             );
 
-            let bounds_err = LastChecked::try_from(zero_seconds).unwrap_err();
+            // Unchecked values are kept as is
+            let unchecked = TimeToConnect::new_unchecked(Duration::from_secs(0));
+            assert_eq!(unchecked.value(), Duration::from_secs(0));
+
+            // and checked values panic at runtime too
+            assert!(std::panic::catch_unwind(|| LastChecked::from_minutes_const(0)).is_err());
+            assert!(std::panic::catch_unwind(|| TimeToConnect::from_secs_const(61)).is_err());
+        }
+
+        #[test]
+        fn integer_constructors() {
             assert_eq!(
-                bounds_err,
-                ParamError::out_of_bounds(zero_seconds, LAST_CHECKED_BOUNDS)
+                LastChecked::from_minutes(1).unwrap().value(),
+                LAST_CHECKED_BOUNDS.0
+            );
+            assert_eq!(
+                LastChecked::from_minutes(60).unwrap().value(),
+                LAST_CHECKED_BOUNDS.1
+            );
+            assert_eq!(
+                TimeToConnect::from_secs(1).unwrap().value(),
+                TIME_TO_CONNECT_BOUNDS.0
+            );
+            assert_eq!(
+                TimeToConnect::from_secs(60).unwrap().value(),
+                TIME_TO_CONNECT_BOUNDS.1
             );
 
-            let bounds_err = LastChecked::try_from(just_over_hour).unwrap_err();
             assert_eq!(
-                bounds_err,
-                ParamError::out_of_bounds(just_over_hour, LAST_CHECKED_BOUNDS)
+                LastChecked::from_minutes(61),
+                Err(last_checked_err(Duration::from_secs(61 * 60)))
+            );
+            assert_eq!(
+                TimeToConnect::from_secs(0),
+                Err(time_to_connect_err(Duration::from_secs(0)))
             );
+            // Huge values don't overflow
+            assert!(LastChecked::from_minutes(u64::MAX).is_err());
         }
 
         #[test]
@@ -230,4 +751,155 @@ mod tests {
             assert_eq!(valid_last_checked.value(), half_hour);
         }
     }
+
+    mod names {
+        use super::*;
+
+        #[test]
+        fn round_trips() {
+            for level in &[Level::Anonymous, Level::Elite, Level::Transparent] {
+                assert_eq!(level.to_string().parse(), Ok(*level));
+                // And the names match serde's
+                assert_eq!(
+                    serde_json::to_string(level).unwrap(),
+                    format!(r#""{}""#, level)
+                );
+            }
+
+            for protocol in &[Protocol::Http, Protocol::Socks4, Protocol::Socks5] {
+                assert_eq!(protocol.to_string().parse(), Ok(*protocol));
+                assert_eq!(
+                    serde_json::to_string(protocol).unwrap(),
+                    format!(r#""{}""#, protocol)
+                );
+            }
+        }
+
+        #[test]
+        fn garbage() {
+            for garbage in &["", "Elite", "socks", " http", "elite "] {
+                let unrecognized = || ParamError::unrecognized(garbage.to_string());
+                assert_eq!(garbage.parse::<Level>(), Err(unrecognized()));
+                assert_eq!(garbage.parse::<Protocol>(), Err(unrecognized()));
+            }
+        }
+    }
+
+    mod countries {
+        use super::*;
+
+        #[test]
+        fn groups() {
+            assert_eq!(CountryGroup::FiveEyes.countries().len(), 5);
+            assert_eq!(CountryGroup::NineEyes.countries().len(), 9);
+            assert_eq!(CountryGroup::FourteenEyes.countries().len(), 14);
+            assert_eq!(CountryGroup::Eu27.countries().len(), 27);
+            assert_eq!(CountryGroup::Asean.countries().len(), 10);
+
+            assert_eq!(
+                Countries::block().group(CountryGroup::FiveEyes),
+                Countries::BlockList("US,GB,CA,AU,NZ".to_string())
+            );
+            assert_eq!(
+                Countries::allow()
+                    .country(Country::JP)
+                    .group(CountryGroup::FiveEyes),
+                Countries::AllowList("JP,US,GB,CA,AU,NZ".to_string())
+            );
+        }
+
+        #[test]
+        fn codes_and_kind() {
+            let allow = Countries::allow().countries(&[Country::CH, Country::ES]);
+            assert_eq!(allow.as_codes(), "CH,ES");
+            assert_eq!(allow.kind(), ListKind::Allow);
+
+            let block = Countries::block().country(Country::CN);
+            assert_eq!(block.as_codes(), "CN");
+            assert_eq!(block.kind(), ListKind::Block);
+
+            // Empty lists still have a kind
+            assert_eq!(Countries::allow().as_codes(), "");
+            assert_eq!(Countries::allow().kind(), ListKind::Allow);
+            assert_eq!(Countries::default().kind(), ListKind::Block);
+        }
+
+        #[test]
+        fn from_iter() {
+            let countries = Countries::block_from_iter(
+                CountryGroup::FourteenEyes
+                    .countries()
+                    .iter()
+                    .copied()
+                    .filter(|country| !CountryGroup::FiveEyes.countries().contains(country)),
+            );
+            assert_eq!(countries.len(), 9);
+            assert!(matches!(countries, Countries::BlockList(_)));
+            assert!(!countries.contains(&Country::US));
+
+            assert_eq!(
+                Countries::allow_from_iter(vec![Country::DE, Country::FR]),
+                Countries::allow().countries(&[Country::DE, Country::FR])
+            );
+            assert!(Countries::allow_from_iter(Vec::new()).is_empty());
+        }
+
+        #[test]
+        fn unspecified() {
+            assert_eq!(
+                Countries::block().try_country(Country::Unspecified),
+                Err(ParamError::unsupported("Unspecified".to_string()))
+            );
+            assert_eq!(
+                Countries::allow().try_countries(&[Country::US, Country::Unspecified]),
+                Err(ParamError::Unsupported {
+                    value: "Unspecified".to_string()
+                })
+            );
+            assert_eq!(
+                Countries::allow().try_countries(&[Country::US, Country::CA]),
+                Ok(Countries::AllowList("US,CA".to_string()))
+            );
+        }
+
+        #[test]
+        #[should_panic]
+        fn from_iter_unspecified() {
+            let _ = Countries::allow_from_iter(vec![Country::US, Country::Unspecified]);
+        }
+
+        #[test]
+        fn len_and_iter() {
+            assert_eq!(Countries::allow().len(), 0);
+            assert_eq!(Countries::block().iter().next(), None);
+
+            let countries = Countries::allow().group(CountryGroup::FiveEyes);
+            assert_eq!(countries.len(), 5);
+            assert_eq!(
+                countries.iter().collect::<Vec<_>>(),
+                CountryGroup::FiveEyes.countries()
+            );
+        }
+
+        #[test]
+        fn remove_and_contains() {
+            let countries = Countries::allow().countries(&[Country::US, Country::CA, Country::MX]);
+            assert!(countries.contains(&Country::CA));
+            assert!(!countries.contains(&Country::Unspecified));
+
+            let countries = countries.remove(Country::CA);
+            assert!(!countries.contains(&Country::CA));
+            assert_eq!(countries, Countries::AllowList("US,MX".to_string()));
+
+            // Removing a country that isn't there is a no-op
+            assert_eq!(countries.clone().remove(Country::JP), countries);
+
+            // And removing everything leaves it empty
+            let countries = countries.remove(Country::US).remove(Country::MX);
+            assert!(countries.is_empty());
+            assert_eq!(countries, Countries::allow());
+            assert!(!Countries::block().contains(&Country::US));
+            assert!(!Countries::block().contains(&Country::Unspecified));
+        }
+    }
 }